@@ -0,0 +1,44 @@
+extern crate nalgebra_glm as glm;
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use filling_polygon::framebuffer::Framebuffer;
+
+fn teapot() -> Vec<glm::Vec3> {
+    vec![
+        glm::Vec3::new(413.0, 177.0, 0.0),
+        glm::Vec3::new(448.0, 159.0, 0.0),
+        glm::Vec3::new(502.0, 88.0, 0.0),
+        glm::Vec3::new(553.0, 53.0, 0.0),
+        glm::Vec3::new(535.0, 36.0, 0.0),
+        glm::Vec3::new(676.0, 37.0, 0.0),
+        glm::Vec3::new(660.0, 52.0, 0.0),
+        glm::Vec3::new(750.0, 145.0, 0.0),
+        glm::Vec3::new(761.0, 179.0, 0.0),
+        glm::Vec3::new(672.0, 192.0, 0.0),
+        glm::Vec3::new(659.0, 214.0, 0.0),
+        glm::Vec3::new(615.0, 214.0, 0.0),
+        glm::Vec3::new(632.0, 230.0, 0.0),
+        glm::Vec3::new(580.0, 230.0, 0.0),
+        glm::Vec3::new(597.0, 215.0, 0.0),
+        glm::Vec3::new(552.0, 214.0, 0.0),
+        glm::Vec3::new(517.0, 144.0, 0.0),
+        glm::Vec3::new(466.0, 180.0, 0.0),
+    ]
+}
+
+fn fill_teapot(c: &mut Criterion) {
+    let mut framebuffer = Framebuffer::new(800, 800);
+    framebuffer.clear();
+    let points = teapot();
+
+    c.bench_function("paint_filled_polygon teapot", |b| {
+        b.iter(|| {
+            framebuffer
+                .paint_filled_polygon(black_box(points.clone()), 0x0a5c36, 0xffffff)
+                .unwrap()
+        })
+    });
+}
+
+criterion_group!(benches, fill_teapot);
+criterion_main!(benches);
@@ -80,7 +80,7 @@ fn pad_buffer(buffer: &[u32], width: usize) -> Vec<u8> {
         .iter()
         .map(|c| c.into())
         .enumerate()
-        .flat_map(|(i, Color { r, g, b })| {
+        .flat_map(|(i, Color { r, g, b, .. })| {
             // The order is not a typo
             // Microsoft do be smoking...
             let vec = vec![b, g, r];
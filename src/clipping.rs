@@ -0,0 +1,275 @@
+/// An axis aligned rectangle used as the clipping region.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub min_x: f32,
+    pub min_y: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+}
+
+impl Rect {
+    /// Whether `point` lies inside the rectangle or on its sides.
+    pub fn contains(&self, point: &glm::Vec3) -> bool {
+        (self.min_x..=self.max_x).contains(&point.x) && (self.min_y..=self.max_y).contains(&point.y)
+    }
+
+    /// Grows the rectangle by `margin` on every side.
+    pub fn expand(&self, margin: f32) -> Rect {
+        Rect {
+            min_x: self.min_x - margin,
+            min_y: self.min_y - margin,
+            max_x: self.max_x + margin,
+            max_y: self.max_y + margin,
+        }
+    }
+}
+
+/// A vertex of a polygon that went through `clip_polygon`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClippedVertex {
+    pub point: glm::Vec3,
+    /// Whether the edge going from this vertex to the next one is part of the original outline.
+    ///
+    /// Edges that were added along the sides of the clipping rectangle are not.
+    pub on_outline: bool,
+}
+
+/// A side of the clipping rectangle.
+#[derive(Debug, Clone, Copy)]
+enum Side {
+    Left(f32),
+    Right(f32),
+    Top(f32),
+    Bottom(f32),
+}
+
+impl Side {
+    fn contains(&self, p: &glm::Vec3) -> bool {
+        match *self {
+            Side::Left(x) => p.x >= x,
+            Side::Right(x) => p.x <= x,
+            Side::Top(y) => p.y >= y,
+            Side::Bottom(y) => p.y <= y,
+        }
+    }
+
+    /// Computes the point where the segment going from `a` to `b` crosses this side.
+    fn intersection(&self, a: &glm::Vec3, b: &glm::Vec3) -> glm::Vec3 {
+        let t = match *self {
+            Side::Left(x) | Side::Right(x) => (x - a.x) / (b.x - a.x),
+            Side::Top(y) | Side::Bottom(y) => (y - a.y) / (b.y - a.y),
+        };
+        let mut point = a + (b - a) * t;
+
+        // Snap the clipped coordinate so rounding errors can't leave it outside the side.
+        match *self {
+            Side::Left(x) | Side::Right(x) => point.x = x,
+            Side::Top(y) | Side::Bottom(y) => point.y = y,
+        }
+
+        point
+    }
+}
+
+/// Clips the closed polygon formed by `points` against `rect` using the Sutherland-Hodgman algorithm.
+///
+/// The result is the part of the polygon inside the rectangle, with its vertices in the same order.
+/// Concave polygons may end up with edges running along the sides of the rectangle,
+/// those are marked so outlines can skip them.
+pub fn clip_polygon(points: &[glm::Vec3], rect: &Rect) -> Vec<ClippedVertex> {
+    let sides = [
+        Side::Left(rect.min_x),
+        Side::Right(rect.max_x),
+        Side::Top(rect.min_y),
+        Side::Bottom(rect.max_y),
+    ];
+
+    let vertices: Vec<ClippedVertex> = points
+        .iter()
+        .map(|point| ClippedVertex {
+            point: *point,
+            on_outline: true,
+        })
+        .collect();
+
+    sides.iter().fold(vertices, |vertices, side| {
+        let mut clipped = vec![];
+
+        let Some(last) = vertices.last() else {
+            return clipped;
+        };
+
+        let mut start = last;
+        for end in &vertices {
+            match (side.contains(&start.point), side.contains(&end.point)) {
+                (true, true) => clipped.push(*end),
+                (true, false) => clipped.push(ClippedVertex {
+                    point: side.intersection(&start.point, &end.point),
+                    on_outline: false,
+                }),
+                (false, true) => {
+                    clipped.push(ClippedVertex {
+                        point: side.intersection(&start.point, &end.point),
+                        on_outline: start.on_outline,
+                    });
+                    clipped.push(*end);
+                }
+                (false, false) => {}
+            }
+
+            start = end;
+        }
+
+        clipped
+    })
+}
+
+/// Clips the segment going from `start` to `end` against `rect` using the Liang-Barsky algorithm.
+///
+/// Returns the part of the segment inside the rectangle, keeping its direction,
+/// or `None` if the segment is completely outside of it.
+pub fn clip_line(start: glm::Vec3, end: glm::Vec3, rect: &Rect) -> Option<(glm::Vec3, glm::Vec3)> {
+    let delta = end - start;
+    let mut t_start: f32 = 0.0;
+    let mut t_end: f32 = 1.0;
+
+    // Each pair describes a side as `p * t <= q`, for the points `start + delta * t` inside it.
+    let sides = [
+        (-delta.x, start.x - rect.min_x),
+        (delta.x, rect.max_x - start.x),
+        (-delta.y, start.y - rect.min_y),
+        (delta.y, rect.max_y - start.y),
+    ];
+
+    for (p, q) in sides {
+        if p == 0.0 {
+            // Parallel to the side, so it's either completely inside or completely outside.
+            if q < 0.0 {
+                return None;
+            }
+            continue;
+        }
+
+        let t = q / p;
+        if p < 0.0 {
+            t_start = t_start.max(t);
+        } else {
+            t_end = t_end.min(t);
+        }
+
+        if t_start > t_end {
+            return None;
+        }
+    }
+
+    Some((start + delta * t_start, start + delta * t_end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECT: Rect = Rect {
+        min_x: 0.0,
+        min_y: 0.0,
+        max_x: 10.0,
+        max_y: 10.0,
+    };
+
+    #[test]
+    fn test_polygon_inside_is_untouched() {
+        let points = vec![
+            glm::Vec3::new(1.0, 1.0, 0.0),
+            glm::Vec3::new(9.0, 1.0, 0.0),
+            glm::Vec3::new(5.0, 9.0, 0.0),
+        ];
+
+        let clipped = clip_polygon(&points, &RECT);
+
+        assert_eq!(clipped.len(), 3);
+        assert!(clipped.iter().all(|v| v.on_outline));
+        assert!(points.iter().all(|p| clipped.iter().any(|v| v.point == *p)));
+    }
+
+    #[test]
+    fn test_corners_outside_are_cut() {
+        let points = vec![
+            glm::Vec3::new(-5.0, 5.0, 0.0),
+            glm::Vec3::new(5.0, -5.0, 0.0),
+            glm::Vec3::new(5.0, 5.0, 0.0),
+        ];
+
+        let clipped = clip_polygon(&points, &RECT);
+        let outline: Vec<(glm::Vec3, glm::Vec3)> = clipped
+            .iter()
+            .zip(clipped.iter().cycle().skip(1))
+            .filter(|(start, end)| start.on_outline && start.point != end.point)
+            .map(|(start, end)| (start.point, end.point))
+            .collect();
+
+        // The visible part is the square going from (0, 0) to (5, 5),
+        // only its right and bottom sides are part of the original outline.
+        assert!(clipped
+            .iter()
+            .all(|v| (0.0..=5.0).contains(&v.point.x) && (0.0..=5.0).contains(&v.point.y)));
+        assert_eq!(
+            outline,
+            vec![
+                (glm::Vec3::new(5.0, 0.0, 0.0), glm::Vec3::new(5.0, 5.0, 0.0)),
+                (glm::Vec3::new(5.0, 5.0, 0.0), glm::Vec3::new(0.0, 5.0, 0.0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_polygon_outside_is_removed() {
+        let points = vec![
+            glm::Vec3::new(-5.0, -5.0, 0.0),
+            glm::Vec3::new(-1.0, -5.0, 0.0),
+            glm::Vec3::new(-1.0, -1.0, 0.0),
+        ];
+
+        assert!(clip_polygon(&points, &RECT).is_empty());
+    }
+
+    #[test]
+    fn test_line_crossing_the_rect_is_cut() {
+        let clipped = clip_line(
+            glm::Vec3::new(-5.0, -5.0, 0.0),
+            glm::Vec3::new(15.0, 15.0, 0.0),
+            &RECT,
+        );
+
+        assert_eq!(
+            clipped,
+            Some((
+                glm::Vec3::new(0.0, 0.0, 0.0),
+                glm::Vec3::new(10.0, 10.0, 0.0)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_line_inside_is_untouched_and_outside_is_removed() {
+        let start = glm::Vec3::new(1.0, 2.0, 0.0);
+        let end = glm::Vec3::new(8.0, 2.0, 0.0);
+
+        assert_eq!(clip_line(start, end, &RECT), Some((start, end)));
+        assert_eq!(
+            clip_line(
+                glm::Vec3::new(-5.0, 6.0, 0.0),
+                glm::Vec3::new(5.0, 16.0, 0.0),
+                &RECT
+            ),
+            None
+        );
+        assert_eq!(
+            clip_line(
+                glm::Vec3::new(-1.0, 11.0, 0.0),
+                glm::Vec3::new(20.0, 11.0, 0.0),
+                &RECT
+            ),
+            None
+        );
+    }
+}
@@ -4,11 +4,35 @@ pub struct Color {
     pub r: u8,
     pub g: u8,
     pub b: u8,
+    /// Opacity of the color, from fully transparent at 0 to fully opaque at 255.
+    pub a: u8,
 }
 
 impl Color {
+    /// Creates an opaque color.
     pub fn new(r: u8, g: u8, b: u8) -> Self {
-        Color { r, g, b }
+        Color::new_rgba(r, g, b, u8::MAX)
+    }
+
+    pub fn new_rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color { r, g, b, a }
+    }
+
+    /// The opacity of the color as a fraction between 0 and 1.
+    pub fn alpha(&self) -> f32 {
+        self.a as f32 / 255.0
+    }
+
+    /// Creates a color from a value formatted exactly as 0xAARRGGBB, where an alpha of 0
+    /// is fully transparent, unlike the conversion from u32.
+    pub fn from_argb(value: u32) -> Self {
+        let [a, r, g, b] = value.to_be_bytes();
+        Color::new_rgba(r, g, b, a)
+    }
+
+    /// Converts the color into a value formatted as 0xAARRGGBB, keeping its alpha.
+    pub fn to_argb(&self) -> u32 {
+        u32::from_be_bytes([self.a, self.r, self.g, self.b])
     }
 
     pub fn black() -> Self {
@@ -21,13 +45,17 @@ impl Color {
 
 /// Converts from a hex u32 into a `Color`.
 ///
+/// The value is read as 0xAARRGGBB. Since a fully transparent color paints nothing,
+/// an alpha of 0 is read as opaque instead, so plain 0xRRGGBB values are opaque colors.
+///
 /// * `value`: The hex u32 to convert into a `Color`.
 fn from_hex_value(value: &u32) -> Color {
+    let a = ((value >> 24) & 0xFF) as u8;
     let r = ((value >> 16) & 0xFF) as u8;
     let g = ((value >> 8) & 0xFF) as u8;
     let b = (value & 0xFF) as u8;
 
-    Color::new(r, g, b)
+    Color::new_rgba(r, g, b, if a == 0 { u8::MAX } else { a })
 }
 
 impl From<u32> for Color {
@@ -46,11 +74,13 @@ impl From<&mut u32> for Color {
     }
 }
 
-/// Converts from a color into a hex u32.
+/// Converts from a color into a hex u32 formatted as 0xRRGGBB, like the pixels of a buffer.
+///
+/// The alpha is dropped, so only the color it would have on top of black at full opacity is kept.
 ///
 /// * `value`: The color to convert into a u32.
 fn to_hex_value(value: &Color) -> u32 {
-    let Color { r, g, b } = value;
+    let Color { r, g, b, .. } = value;
     (*r as u32) << 16 | (*g as u32) << 8 | (*b as u32)
 }
 
@@ -76,17 +106,20 @@ impl std::ops::Add for Color {
     type Output = Color;
 
     fn add(self, rhs: Self) -> Self::Output {
-        let Color { r, g, b } = self;
+        let Color { r, g, b, a } = self;
         let Color {
             r: r2,
             g: g2,
             b: b2,
+            ..
         } = rhs;
 
-        Color::new(
+        // The alpha isn't a channel to mix, the result keeps the opacity of `self`.
+        Color::new_rgba(
             r.saturating_add(r2),
             g.saturating_add(g2),
             b.saturating_add(b2),
+            a,
         )
     }
 }
@@ -95,12 +128,13 @@ impl std::ops::Mul<f32> for Color {
     type Output = Color;
 
     fn mul(self, factor: f32) -> Self::Output {
-        let Color { r, g, b } = self;
+        let Color { r, g, b, a } = self;
 
-        Color::new(
+        Color::new_rgba(
             (r as f32 * factor).clamp(0.0, 255.0) as u8,
             (g as f32 * factor).clamp(0.0, 255.0) as u8,
             (b as f32 * factor).clamp(0.0, 255.0) as u8,
+            a,
         )
     }
 }
@@ -109,17 +143,19 @@ impl std::ops::Sub for Color {
     type Output = Color;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        let Color { r, g, b } = self;
+        let Color { r, g, b, a } = self;
         let Color {
             r: r2,
             g: g2,
             b: b2,
+            ..
         } = rhs;
 
-        Color::new(
+        Color::new_rgba(
             r.saturating_sub(r2),
             g.saturating_sub(g2),
             b.saturating_sub(b2),
+            a,
         )
     }
 }
@@ -133,7 +169,7 @@ mod tests {
         let color = Color::new(5, 100, 1);
         let factor = -1.5;
 
-        let Color { r, g, b } = color * factor;
+        let Color { r, g, b, .. } = color * factor;
 
         assert_eq!(r, 0);
         assert_eq!(g, 0);
@@ -145,10 +181,34 @@ mod tests {
         let color = Color::new(255, 100, 1);
         let factor = 100000.0;
 
-        let Color { r, g, b } = color * factor;
+        let Color { r, g, b, .. } = color * factor;
 
         assert_eq!(r, 255);
         assert_eq!(g, 255);
         assert_eq!(b, 255);
     }
+
+    #[test]
+    fn test_arithmetic_keeps_alpha() {
+        let Color { r, g, b, a } = Color::white() - Color::new(0, 0, 255);
+        assert_eq!((r, g, b, a), (255, 255, 0, 255));
+
+        let Color { r, g, b, a } = Color::white() * 0.5;
+        assert_eq!((r, g, b, a), (127, 127, 127, 255));
+
+        let Color { a, .. } = Color::new_rgba(10, 20, 30, 128) + Color::white();
+        assert_eq!(a, 128);
+    }
+
+    #[test]
+    fn test_hex_values_with_alpha() {
+        let Color { r, g, b, a } = Color::from(0x80ff8000);
+        assert_eq!((r, g, b, a), (255, 128, 0, 128));
+
+        // Without an alpha byte colors are opaque.
+        assert_eq!(Color::from(0xff8000).a, 255);
+        assert_eq!(u32::from(Color::new_rgba(1, 2, 3, 4)), 0x010203);
+        assert_eq!(Color::from_argb(0x00010203).a, 0);
+        assert_eq!(Color::new_rgba(1, 2, 3, 4).to_argb(), 0x04010203);
+    }
 }
@@ -0,0 +1,255 @@
+use crate::color::Color;
+
+/// Porter-Duff operator used to combine the color being painted, the source,
+/// with the color a pixel already has, the destination.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompositeOperation {
+    /// Neither the source nor the destination are kept, the pixel becomes transparent.
+    Clear,
+    /// Only the source is kept.
+    Source,
+    /// Only the destination is kept.
+    Destination,
+    /// The source is painted on top of the destination.
+    #[default]
+    SourceOver,
+    /// The source is painted behind the destination.
+    DestinationOver,
+    /// The source is kept where the destination is opaque.
+    SourceIn,
+    /// The destination is kept where the source is opaque.
+    DestinationIn,
+    /// The source is kept where the destination is transparent.
+    SourceOut,
+    /// The destination is kept where the source is transparent.
+    DestinationOut,
+    /// The source is painted on top of the destination, but only where the destination is opaque.
+    SourceAtop,
+    /// The destination is painted on top of the source, but only where the source is opaque.
+    DestinationAtop,
+    /// Each one is kept where the other one is transparent.
+    Xor,
+}
+
+impl CompositeOperation {
+    /// Computes the fractions of the source and the destination that are kept,
+    /// given the alpha of each one.
+    fn factors(&self, source_alpha: f32, destination_alpha: f32) -> (f32, f32) {
+        match self {
+            CompositeOperation::Clear => (0.0, 0.0),
+            CompositeOperation::Source => (1.0, 0.0),
+            CompositeOperation::Destination => (0.0, 1.0),
+            CompositeOperation::SourceOver => (1.0, 1.0 - source_alpha),
+            CompositeOperation::DestinationOver => (1.0 - destination_alpha, 1.0),
+            CompositeOperation::SourceIn => (destination_alpha, 0.0),
+            CompositeOperation::DestinationIn => (0.0, source_alpha),
+            CompositeOperation::SourceOut => (1.0 - destination_alpha, 0.0),
+            CompositeOperation::DestinationOut => (0.0, 1.0 - source_alpha),
+            CompositeOperation::SourceAtop => (destination_alpha, 1.0 - source_alpha),
+            CompositeOperation::DestinationAtop => (1.0 - destination_alpha, source_alpha),
+            CompositeOperation::Xor => (1.0 - destination_alpha, 1.0 - source_alpha),
+        }
+    }
+
+    /// Combines `source` with `destination`.
+    ///
+    /// Pixels that are only partially covered by the shape being painted keep
+    /// the rest of the destination untouched.
+    ///
+    /// * `coverage`: Fraction of the pixel covered, between 0 and 1.
+    pub fn composite(&self, source: Color, destination: Color, coverage: f32) -> Color {
+        let coverage = coverage.clamp(0.0, 1.0);
+        let source_alpha = source.alpha();
+        let destination_alpha = destination.alpha();
+        let (source_factor, destination_factor) = self.factors(source_alpha, destination_alpha);
+
+        let source_weight = source_alpha * source_factor * coverage;
+        let destination_weight =
+            destination_alpha * (destination_factor * coverage + 1.0 - coverage);
+        let alpha = source_weight + destination_weight;
+
+        // Mixes the channels weighted by their alpha, then divides the alpha back out.
+        let channel = |source: u8, destination: u8| {
+            if alpha <= 0.0 {
+                return 0;
+            }
+
+            ((source as f32 * source_weight + destination as f32 * destination_weight) / alpha)
+                .round()
+                .clamp(0.0, 255.0) as u8
+        };
+
+        Color::new_rgba(
+            channel(source.r, destination.r),
+            channel(source.g, destination.g),
+            channel(source.b, destination.b),
+            (alpha * 255.0).round().clamp(0.0, 255.0) as u8,
+        )
+    }
+}
+
+/// How the color being painted is mixed with the color a pixel already has, the backdrop,
+/// before being composited on top of it.
+///
+/// Each channel is mixed on its own. Where the backdrop is translucent the source shows through
+/// unchanged, in proportion to how transparent the backdrop is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlendMode {
+    /// The source is painted as is.
+    #[default]
+    Normal,
+    /// Multiplies the channels, so the result is always at least as dark as both colors.
+    Multiply,
+    /// Multiplies the complements of the channels, so the result is always at least as light
+    /// as both colors.
+    Screen,
+    /// Multiplies dark backdrops and screens light ones, keeping the contrast of the backdrop.
+    Overlay,
+    /// Keeps the darkest of both channels.
+    Darken,
+    /// Keeps the lightest of both channels.
+    Lighten,
+    /// Subtracts the darkest channel from the lightest one.
+    Difference,
+    /// Brightens the backdrop to reflect the source.
+    ColorDodge,
+    /// Darkens the backdrop to reflect the source.
+    ColorBurn,
+}
+
+impl BlendMode {
+    /// Mixes a single channel of the `source` and the `backdrop`, both between 0 and 1.
+    fn blend_channel(&self, source: f32, backdrop: f32) -> f32 {
+        let multiply = |a: f32, b: f32| a * b;
+        let screen = |a: f32, b: f32| a + b - a * b;
+
+        match self {
+            BlendMode::Normal => source,
+            BlendMode::Multiply => multiply(source, backdrop),
+            BlendMode::Screen => screen(source, backdrop),
+            BlendMode::Overlay if backdrop <= 0.5 => multiply(source, 2.0 * backdrop),
+            BlendMode::Overlay => screen(source, 2.0 * backdrop - 1.0),
+            BlendMode::Darken => source.min(backdrop),
+            BlendMode::Lighten => source.max(backdrop),
+            BlendMode::Difference => (source - backdrop).abs(),
+            BlendMode::ColorDodge if backdrop == 0.0 => 0.0,
+            BlendMode::ColorDodge if source == 1.0 => 1.0,
+            BlendMode::ColorDodge => (backdrop / (1.0 - source)).min(1.0),
+            BlendMode::ColorBurn if backdrop == 1.0 => 1.0,
+            BlendMode::ColorBurn if source == 0.0 => 0.0,
+            BlendMode::ColorBurn => 1.0 - ((1.0 - backdrop) / source).min(1.0),
+        }
+    }
+
+    /// Mixes `source` with `backdrop`.
+    ///
+    /// Returns: The color to composite in place of `source`, with the same alpha.
+    pub fn blend(&self, source: Color, backdrop: Color) -> Color {
+        if *self == BlendMode::Normal {
+            return source;
+        }
+
+        let backdrop_alpha = backdrop.alpha();
+        let channel = |source: u8, backdrop: u8| {
+            let source = source as f32 / 255.0;
+            let mixed = self.blend_channel(source, backdrop as f32 / 255.0);
+            let result = (1.0 - backdrop_alpha) * source + backdrop_alpha * mixed;
+
+            (result * 255.0).round().clamp(0.0, 255.0) as u8
+        };
+
+        Color::new_rgba(
+            channel(source.r, backdrop.r),
+            channel(source.g, backdrop.g),
+            channel(source.b, backdrop.b),
+            source.a,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgba(color: Color) -> (u8, u8, u8, u8) {
+        (color.r, color.g, color.b, color.a)
+    }
+
+    #[test]
+    fn test_operators_on_opaque_and_transparent_pixels() {
+        let red = Color::new(255, 0, 0);
+        let blue = Color::new(0, 0, 255);
+        let clear = Color::new_rgba(0, 0, 0, 0);
+
+        let composite = |operation: CompositeOperation, destination: Color| {
+            rgba(operation.composite(red, destination, 1.0))
+        };
+
+        assert_eq!(
+            composite(CompositeOperation::SourceOver, blue),
+            (255, 0, 0, 255)
+        );
+        assert_eq!(
+            composite(CompositeOperation::DestinationOver, blue),
+            (0, 0, 255, 255)
+        );
+        assert_eq!(composite(CompositeOperation::Clear, blue), (0, 0, 0, 0));
+        assert_eq!(
+            composite(CompositeOperation::SourceIn, blue),
+            (255, 0, 0, 255)
+        );
+        assert_eq!(composite(CompositeOperation::SourceIn, clear), (0, 0, 0, 0));
+        assert_eq!(
+            composite(CompositeOperation::SourceOut, clear),
+            (255, 0, 0, 255)
+        );
+        assert_eq!(
+            composite(CompositeOperation::DestinationOut, blue),
+            (0, 0, 0, 0)
+        );
+        assert_eq!(composite(CompositeOperation::Xor, blue), (0, 0, 0, 0));
+        assert_eq!(composite(CompositeOperation::Xor, clear), (255, 0, 0, 255));
+    }
+
+    #[test]
+    fn test_translucent_source_and_partial_coverage() {
+        let translucent_red = Color::new_rgba(255, 0, 0, 128);
+        let blue = Color::new(0, 0, 255);
+
+        let over = CompositeOperation::SourceOver.composite(translucent_red, blue, 1.0);
+        assert_eq!(rgba(over), (128, 0, 127, 255));
+
+        // Copying only replaces the covered half of the pixel.
+        let copy = CompositeOperation::Source.composite(translucent_red, blue, 0.5);
+        assert_eq!(rgba(copy), (85, 0, 170, 192));
+
+        let atop = CompositeOperation::SourceAtop.composite(translucent_red, blue, 1.0);
+        assert_eq!(rgba(atop), rgba(over));
+    }
+
+    #[test]
+    fn test_blend_modes() {
+        let source = Color::new(255, 128, 0);
+        let backdrop = Color::new(128, 128, 255);
+
+        let blend = |mode: BlendMode| {
+            let Color { r, g, b, .. } = mode.blend(source, backdrop);
+            (r, g, b)
+        };
+
+        assert_eq!(blend(BlendMode::Normal), (255, 128, 0));
+        assert_eq!(blend(BlendMode::Multiply), (128, 64, 0));
+        assert_eq!(blend(BlendMode::Screen), (255, 192, 255));
+        assert_eq!(blend(BlendMode::Overlay), (255, 128, 255));
+        assert_eq!(blend(BlendMode::Darken), (128, 128, 0));
+        assert_eq!(blend(BlendMode::Lighten), (255, 128, 255));
+        assert_eq!(blend(BlendMode::Difference), (127, 0, 255));
+        assert_eq!(blend(BlendMode::ColorDodge), (255, 255, 255));
+        assert_eq!(blend(BlendMode::ColorBurn), (128, 2, 255));
+
+        // A transparent backdrop leaves the source untouched.
+        let transparent = Color::new_rgba(128, 128, 255, 0);
+        let Color { r, g, b, .. } = BlendMode::Multiply.blend(source, transparent);
+        assert_eq!((r, g, b), (255, 128, 0));
+    }
+}
@@ -0,0 +1,368 @@
+use std::f32::consts::PI;
+
+/// Default maximum distance in pixels between a curve and the segments approximating it.
+pub const CURVE_TOLERANCE: f32 = 0.25;
+
+/// Amount of times a curve can be split in half while flattening it.
+///
+/// Deep enough for any curve that fits in a framebuffer, while still bounding the work done
+/// on degenerate curves whose control points lie far away.
+const MAX_SUBDIVISIONS: u32 = 16;
+
+/// Amount of straight lines an ellipse or one of its arcs can be approximated with.
+///
+/// Matches the most pieces a Bézier curve can be split into, so tiny or zero tolerances
+/// still give a bounded amount of vertices.
+const MAX_SEGMENTS: usize = 1 << MAX_SUBDIVISIONS;
+
+/// A piece of a `Path`, going from the point where the previous piece ended to its last point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment {
+    /// A straight line to the given point.
+    Line(glm::Vec3),
+    /// A quadratic Bézier curve with a control point followed by its endpoint.
+    Quadratic(glm::Vec3, glm::Vec3),
+    /// A cubic Bézier curve with two control points followed by its endpoint.
+    Cubic(glm::Vec3, glm::Vec3, glm::Vec3),
+}
+
+/// A shape made of straight lines and Bézier curves joined end to end.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub start: glm::Vec3,
+    pub segments: Vec<Segment>,
+    /// Whether the path goes back from its last point to `start`.
+    pub closed: bool,
+}
+
+impl Path {
+    /// Creates an open path starting at `start` without any segments.
+    pub fn new(start: glm::Vec3) -> Self {
+        Path {
+            start,
+            segments: vec![],
+            closed: false,
+        }
+    }
+
+    pub fn line_to(&mut self, point: glm::Vec3) {
+        self.segments.push(Segment::Line(point));
+    }
+
+    pub fn quadratic_to(&mut self, control: glm::Vec3, end: glm::Vec3) {
+        self.segments.push(Segment::Quadratic(control, end));
+    }
+
+    pub fn cubic_to(&mut self, control1: glm::Vec3, control2: glm::Vec3, end: glm::Vec3) {
+        self.segments.push(Segment::Cubic(control1, control2, end));
+    }
+
+    /// Connects the last point of the path back to its start.
+    pub fn close(&mut self) {
+        self.closed = true;
+    }
+
+    /// Approximates the path with straight lines, none of them further than `tolerance`
+    /// pixels away from the curve they replace.
+    ///
+    /// Returns: The vertices of the approximation, starting with `start`.
+    /// Closed paths don't repeat `start` at the end.
+    pub fn flatten(&self, tolerance: f32) -> Vec<glm::Vec3> {
+        let mut points = vec![self.start];
+
+        for segment in &self.segments {
+            let from = points[points.len() - 1];
+            match *segment {
+                Segment::Line(end) => points.push(end),
+                Segment::Quadratic(control, end) => points.extend(
+                    flatten_quadratic(from, control, end, tolerance)
+                        .into_iter()
+                        .skip(1),
+                ),
+                Segment::Cubic(control1, control2, end) => points.extend(
+                    flatten_cubic(from, control1, control2, end, tolerance)
+                        .into_iter()
+                        .skip(1),
+                ),
+            }
+        }
+
+        if self.closed && points.len() > 1 && points[points.len() - 1] == self.start {
+            points.pop();
+        }
+
+        points
+    }
+}
+
+/// Approximates the quadratic Bézier curve going from `start` to `end` with straight lines.
+///
+/// See `flatten_cubic` for how the curve is split.
+pub fn flatten_quadratic(
+    start: glm::Vec3,
+    control: glm::Vec3,
+    end: glm::Vec3,
+    tolerance: f32,
+) -> Vec<glm::Vec3> {
+    // Every quadratic curve is also a cubic one with these control points.
+    let control1 = start + (control - start) * (2.0 / 3.0);
+    let control2 = end + (control - end) * (2.0 / 3.0);
+
+    flatten_cubic(start, control1, control2, end, tolerance)
+}
+
+/// Approximates the cubic Bézier curve going from `start` to `end` with straight lines.
+///
+/// The curve is split in half with de Casteljau's algorithm until each piece is flat enough
+/// to be replaced by its chord, so flat parts get few vertices and tight bends get many.
+///
+/// Returns: The vertices of the approximation, including both `start` and `end`.
+pub fn flatten_cubic(
+    start: glm::Vec3,
+    control1: glm::Vec3,
+    control2: glm::Vec3,
+    end: glm::Vec3,
+    tolerance: f32,
+) -> Vec<glm::Vec3> {
+    let mut points = vec![start];
+    subdivide([start, control1, control2, end], tolerance, 0, &mut points);
+
+    points
+}
+
+/// Approximates the ellipse centered on `center` with a polygon whose edges are
+/// no further than `tolerance` from it.
+///
+/// * `rotation`: Angle in radians between the x axis and the axis with `radius_x`,
+///   clockwise on the screen.
+pub fn flatten_ellipse(
+    center: glm::Vec3,
+    radius_x: f32,
+    radius_y: f32,
+    rotation: f32,
+    tolerance: f32,
+) -> Vec<glm::Vec3> {
+    let step = angle_step(radius_x.max(radius_y), tolerance);
+    let sides = ((2.0 * PI / step).ceil() as usize).clamp(8, MAX_SEGMENTS);
+    let (sin, cos) = rotation.sin_cos();
+
+    (0..sides)
+        .map(|i| {
+            let angle = 2.0 * PI * i as f32 / sides as f32;
+            let x = angle.cos() * radius_x;
+            let y = angle.sin() * radius_y;
+            center + glm::Vec3::new(x * cos - y * sin, x * sin + y * cos, 0.0)
+        })
+        .collect()
+}
+
+/// Approximates the arc of the ellipse centered on `center` with straight lines
+/// no further than `tolerance` from it.
+///
+/// * `start_angle`: Angle in radians where the arc starts, measured from the x axis
+///   and going clockwise on the screen.
+/// * `sweep_angle`: Angle in radians covered by the arc, negative values go counterclockwise.
+///
+/// Returns: The vertices of the approximation, including both ends of the arc.
+pub fn flatten_arc(
+    center: glm::Vec3,
+    radius_x: f32,
+    radius_y: f32,
+    start_angle: f32,
+    sweep_angle: f32,
+    tolerance: f32,
+) -> Vec<glm::Vec3> {
+    let sweep_angle = sweep_angle.clamp(-2.0 * PI, 2.0 * PI);
+    let step = angle_step(radius_x.max(radius_y), tolerance);
+    let segments = ((sweep_angle.abs() / step).ceil() as usize).clamp(1, MAX_SEGMENTS);
+
+    (0..=segments)
+        .map(|i| {
+            let angle = start_angle + sweep_angle * i as f32 / segments as f32;
+            center + glm::Vec3::new(angle.cos() * radius_x, angle.sin() * radius_y, 0.0)
+        })
+        .collect()
+}
+
+/// Computes the largest angle a chord of the circle with the given `radius` can span
+/// while staying no further than `tolerance` from the circle.
+fn angle_step(radius: f32, tolerance: f32) -> f32 {
+    2.0 * (1.0 - tolerance / radius).clamp(-1.0, 1.0).acos()
+}
+
+/// Pushes the vertices approximating the cubic curve with the given control `points`,
+/// except for the first one.
+fn subdivide(points: [glm::Vec3; 4], tolerance: f32, depth: u32, out: &mut Vec<glm::Vec3>) {
+    let [p0, p1, p2, p3] = points;
+
+    if depth >= MAX_SUBDIVISIONS || flatness(&points) <= tolerance {
+        out.push(p3);
+        return;
+    }
+
+    let p01 = (p0 + p1) * 0.5;
+    let p12 = (p1 + p2) * 0.5;
+    let p23 = (p2 + p3) * 0.5;
+    let p012 = (p01 + p12) * 0.5;
+    let p123 = (p12 + p23) * 0.5;
+    let middle = (p012 + p123) * 0.5;
+
+    subdivide([p0, p01, p012, middle], tolerance, depth + 1, out);
+    subdivide([middle, p123, p23, p3], tolerance, depth + 1, out);
+}
+
+/// Computes an upper bound of how far the cubic curve with the given control `points`
+/// strays from its chord.
+///
+/// The curve never gets further than three quarters of the distance from
+/// the farthest control point to the chord.
+fn flatness(points: &[glm::Vec3; 4]) -> f32 {
+    let [p0, p1, p2, p3] = *points;
+    let chord = p3 - p0;
+    let length = glm::length(&chord);
+
+    let distance = |p: glm::Vec3| {
+        let offset = p - p0;
+        if length == 0.0 {
+            glm::length(&offset)
+        } else {
+            (chord.x * offset.y - chord.y * offset.x).abs() / length
+        }
+    };
+
+    0.75 * distance(p1).max(distance(p2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Evaluates the cubic curve with the given control points at `t`.
+    fn cubic_at(points: [glm::Vec3; 4], t: f32) -> glm::Vec3 {
+        let s = 1.0 - t;
+        points[0] * (s * s * s)
+            + points[1] * (3.0 * s * s * t)
+            + points[2] * (3.0 * s * t * t)
+            + points[3] * (t * t * t)
+    }
+
+    /// Computes the distance from `p` to the closest point of the polyline formed by `points`.
+    fn distance_to_polyline(points: &[glm::Vec3], p: glm::Vec3) -> f32 {
+        points
+            .windows(2)
+            .map(|pair| {
+                let segment = pair[1] - pair[0];
+                let t = (glm::dot(&(p - pair[0]), &segment) / glm::dot(&segment, &segment))
+                    .clamp(0.0, 1.0);
+                glm::length(&(pair[0] + segment * t - p))
+            })
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    #[test]
+    fn test_flattened_cubic_stays_within_tolerance() {
+        let control = [
+            glm::Vec3::new(10.0, 90.0, 0.0),
+            glm::Vec3::new(30.0, 0.0, 0.0),
+            glm::Vec3::new(70.0, 180.0, 0.0),
+            glm::Vec3::new(90.0, 10.0, 0.0),
+        ];
+
+        let coarse = flatten_cubic(control[0], control[1], control[2], control[3], 2.0);
+        let fine = flatten_cubic(control[0], control[1], control[2], control[3], 0.1);
+
+        assert!(coarse.len() < fine.len());
+        assert_eq!(fine.first(), Some(&control[0]));
+        assert_eq!(fine.last(), Some(&control[3]));
+        for i in 0..=100 {
+            let point = cubic_at(control, i as f32 / 100.0);
+            assert!(distance_to_polyline(&fine, point) <= 0.1);
+        }
+    }
+
+    #[test]
+    fn test_straight_curves_are_not_split() {
+        let start = glm::Vec3::new(0.0, 0.0, 0.0);
+        let end = glm::Vec3::new(10.0, 10.0, 0.0);
+
+        let points = flatten_quadratic(start, glm::Vec3::new(5.0, 5.0, 0.0), end, 0.25);
+
+        assert_eq!(points, vec![start, end]);
+    }
+
+    #[test]
+    fn test_closed_path_does_not_repeat_its_start() {
+        let start = glm::Vec3::new(0.0, 0.0, 0.0);
+        let mut path = Path::new(start);
+        path.line_to(glm::Vec3::new(10.0, 0.0, 0.0));
+        path.quadratic_to(
+            glm::Vec3::new(10.0, 10.0, 0.0),
+            glm::Vec3::new(0.0, 10.0, 0.0),
+        );
+        path.line_to(start);
+        path.close();
+
+        let points = path.flatten(0.25);
+
+        assert!(points.len() > 4);
+        assert_eq!(points[0], start);
+        assert_ne!(points[points.len() - 1], start);
+        assert_eq!(points[1], glm::Vec3::new(10.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_flattened_ellipse_is_rotated() {
+        let center = glm::Vec3::new(50.0, 50.0, 0.0);
+        let points = flatten_ellipse(center, 20.0, 5.0, PI / 2.0, 0.25);
+
+        assert!(points.len() > 8);
+        assert!((points[0] - glm::Vec3::new(50.0, 70.0, 0.0)).abs().max() < 1e-4);
+        assert!(
+            points
+                .iter()
+                .all(|p| (p.x - center.x).abs() <= 5.0 + 1e-4
+                    && (p.y - center.y).abs() <= 20.0 + 1e-4)
+        );
+    }
+
+    #[test]
+    fn test_flattened_ellipse_with_zero_tolerance() {
+        let center = glm::Vec3::new(0.0, 0.0, 0.0);
+
+        assert_eq!(
+            flatten_ellipse(center, 100.0, 50.0, 0.0, 0.0).len(),
+            MAX_SEGMENTS
+        );
+        assert_eq!(
+            flatten_ellipse(center, 100.0, 50.0, 0.0, -1.0).len(),
+            MAX_SEGMENTS
+        );
+    }
+
+    #[test]
+    fn test_flattened_arc_with_zero_tolerance() {
+        let center = glm::Vec3::new(0.0, 0.0, 0.0);
+        let points = flatten_arc(center, 100.0, 100.0, 0.0, 1.0, 0.0);
+
+        assert_eq!(points.len(), MAX_SEGMENTS + 1);
+        assert_eq!(points[0], glm::Vec3::new(100.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_flattened_arc_goes_from_start_to_end() {
+        let center = glm::Vec3::new(0.0, 0.0, 0.0);
+        let points = flatten_arc(center, 10.0, 10.0, 0.0, -PI / 2.0, 0.25);
+
+        assert!(points.len() > 2);
+        assert_eq!(points[0], glm::Vec3::new(10.0, 0.0, 0.0));
+        assert!(
+            (points[points.len() - 1] - glm::Vec3::new(0.0, -10.0, 0.0))
+                .abs()
+                .max()
+                < 1e-4
+        );
+        assert!(points
+            .iter()
+            .all(|p| p.x >= -1e-4 && p.y <= 1e-4 && (glm::length(p) - 10.0).abs() < 1e-4));
+    }
+}
@@ -1,8 +1,22 @@
-use std::{ops::Deref, usize};
+use std::{
+    collections::VecDeque,
+    f32::consts::{PI, SQRT_2},
+};
 
 use nalgebra_glm::Vec3;
 
-use crate::{are_equal, bmp::write_bmp_file, color::Color};
+use crate::{
+    bmp::write_bmp_file,
+    clipping::{clip_line, clip_polygon, Rect},
+    color::Color,
+    compositing::{BlendMode, CompositeOperation},
+    curve::{
+        flatten_arc, flatten_cubic, flatten_ellipse, flatten_quadratic, Path, CURVE_TOLERANCE,
+    },
+    edge_function,
+    scanline::{EdgeTable, FillRule, Span},
+    stroke::{dash_path, stroke_outlines, LineCap, LineJoin, StrokeStyle},
+};
 
 #[derive(Debug)]
 pub struct Framebuffer {
@@ -11,13 +25,22 @@ pub struct Framebuffer {
     buffer: Buffer,
     background_color: Color,
     current_color: Color,
+    fill_rule: FillRule,
+    anti_aliasing: bool,
+    stroke_style: StrokeStyle,
+    curve_tolerance: f32,
+    composite_operation: CompositeOperation,
+    blend_mode: BlendMode,
     empty_buffer: Vec<u32>,
 }
 
 type Buffer = Vec<u32>;
 
+/// Amount of scanlines sampled inside each pixel row when filling with anti aliasing.
+const ANTI_ALIASING_SAMPLES: u32 = 16;
+
 fn create_filled_buffer(width: &usize, height: &usize, color: &Color) -> Buffer {
-    let color_hex: u32 = color.into();
+    let color_hex = color.to_argb();
 
     (0..(width * height)).map(|_| color_hex).collect()
 }
@@ -42,16 +65,80 @@ pub enum GetColorErrors {
     YTooLarge,
 }
 
+/// Which neighbours of a pixel are considered connected to it when filling a region.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Only the pixels above, below, left and right.
+    Four,
+    /// The four direct neighbours plus the four diagonal ones.
+    Eight,
+}
+
+impl Connectivity {
+    fn offsets(&self) -> &'static [(isize, isize)] {
+        match self {
+            Connectivity::Four => &[(0, -1), (-1, 0), (1, 0), (0, 1)],
+            Connectivity::Eight => &[
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+                (-1, 0),
+                (1, 0),
+                (-1, 1),
+                (0, 1),
+                (1, 1),
+            ],
+        }
+    }
+}
+
+/// A point to paint along with how much of its pixel is covered by the shape being drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fragment {
+    pub point: glm::Vec3,
+    /// Fraction of the pixel covered, between 0 and 1.
+    pub coverage: f32,
+}
+
+impl Fragment {
+    /// Creates a fragment that fully covers the pixel of `point`.
+    fn solid(point: glm::Vec3) -> Self {
+        Fragment {
+            point,
+            coverage: 1.0,
+        }
+    }
+}
+
+/// The fragments of a shape waiting to be painted on a framebuffer.
+///
+/// Fragments are produced one at a time, as the canvas is iterated or painted, so drawing
+/// a line or a hairline outline doesn't need to store all of its points.
+/// Wide outlines are scan converted when the canvas is created.
+/// Iterating the canvas yields the fragments without painting them.
 pub struct Canvas<'a> {
-    data: Vec<glm::Vec3>,
+    fragments: Box<dyn Iterator<Item = Fragment> + 'a>,
     owner: &'a mut Framebuffer,
 }
 
 impl<'a> Canvas<'a> {
+    /// Paints every remaining fragment of the canvas.
+    ///
+    /// Fragments that partially cover their pixel are blended with the color it already had.
     pub fn paint(self) -> Result<(), PaintPointErrors> {
-        let Canvas { data, owner } = self;
+        let Canvas { fragments, owner } = self;
+
+        fragments
+            .into_iter()
+            .try_for_each(|f| owner.blend_point(f.point, f.coverage))
+    }
+}
+
+impl Iterator for Canvas<'_> {
+    type Item = Fragment;
 
-        data.into_iter().try_for_each(|p| owner.paint_point(p))
+    fn next(&mut self) -> Option<Fragment> {
+        self.fragments.next()
     }
 }
 
@@ -66,6 +153,12 @@ impl Framebuffer {
             buffer: vec![],
             background_color,
             current_color,
+            fill_rule: FillRule::default(),
+            anti_aliasing: false,
+            stroke_style: StrokeStyle::default(),
+            curve_tolerance: CURVE_TOLERANCE,
+            composite_operation: CompositeOperation::default(),
+            blend_mode: BlendMode::default(),
             empty_buffer: create_filled_buffer(&width, &height, &Color::black()),
         }
     }
@@ -82,34 +175,113 @@ impl Framebuffer {
     ///
     /// The paint origin is located on the top left corner of the window.
     ///
-    /// The color used is the one provided by `current_color`.
+    /// The color used is the one provided by `current_color`. Translucent colors are
+    /// blended on top of the color the point already had.
     pub fn paint_point(&mut self, point: glm::Vec3) -> Result<(), PaintPointErrors> {
+        self.blend_point(point, 1.0)
+    }
+
+    /// Colors a point in the given location, mixing `current_color` with the color the point
+    /// already had according to how much of the pixel is covered and the alpha of the color.
+    ///
+    /// * `coverage`: Fraction of the pixel covered, between 0 and 1.
+    pub fn blend_point(&mut self, point: glm::Vec3, coverage: f32) -> Result<(), PaintPointErrors> {
+        let index = self.point_index(point)?;
+        self.buffer[index] = composite(
+            self.buffer[index],
+            self.current_color,
+            coverage,
+            self.composite_operation,
+            self.blend_mode,
+        );
+
+        Ok(())
+    }
+
+    /// Computes the index inside the buffer of the pixel that contains `point`.
+    fn point_index(&self, point: glm::Vec3) -> Result<usize, PaintPointErrors> {
+        let Framebuffer { width, height, .. } = self;
+        let x = point.x;
+        let y = point.y;
+
+        if x < 0.0 {
+            Err(PaintPointErrors::XTooSmall)?
+        }
+
+        if y < 0.0 {
+            Err(PaintPointErrors::YTooSmall)?
+        }
+
+        let x = x.round() as usize;
+        let y = y.round() as usize;
+
+        match (x < *width, y < *height) {
+            (false, _) => Err(PaintPointErrors::XTooLarge),
+            (_, false) => Err(PaintPointErrors::YTooLarge),
+            _ => Ok(y * *width + x),
+        }
+    }
+
+    /// Colors every pixel whose center lies inside the given span, following the top-left rule.
+    ///
+    /// When the color simply replaces the pixels, like opaque colors painted with
+    /// `CompositeOperation::SourceOver` and `BlendMode::Normal` do, the whole row is written at once
+    /// instead of painting each pixel with `paint_point`.
+    ///
+    /// The color used is the one provided by `current_color`.
+    fn paint_span(&mut self, span: Span) -> Result<(), PaintPointErrors> {
         let Framebuffer {
             width,
             height,
             buffer,
             current_color,
+            composite_operation,
+            blend_mode,
             ..
         } = self;
-        let x = point.x;
-        let y = point.y;
+        let pixels = span.pixels();
 
-        if x < 0.0 {
+        if pixels.is_empty() {
+            return Ok(());
+        }
+
+        if pixels.start < 0 {
             Err(PaintPointErrors::XTooSmall)?
         }
 
-        if y < 0.0 {
+        if span.y < 0.0 {
             Err(PaintPointErrors::YTooSmall)?
         }
 
-        let x = x.round() as usize;
-        let y = y.round() as usize;
+        let start = pixels.start as usize;
+        let end = pixels.end as usize;
+        let y = span.y as usize;
 
-        match (x <= *width, y <= *height) {
+        match (end <= *width, y < *height) {
             (false, _) => Err(PaintPointErrors::XTooLarge),
             (_, false) => Err(PaintPointErrors::YTooLarge),
             _ => {
-                buffer[y * *width + x] = current_color.into();
+                let row = &mut buffer[y * *width + start..y * *width + end];
+                let replaces_pixels = *blend_mode == BlendMode::Normal
+                    && match composite_operation {
+                        CompositeOperation::Source => true,
+                        CompositeOperation::SourceOver => current_color.a == u8::MAX,
+                        _ => false,
+                    };
+
+                if replaces_pixels {
+                    row.fill(current_color.to_argb());
+                } else {
+                    row.iter_mut().for_each(|pixel| {
+                        *pixel = composite(
+                            *pixel,
+                            *current_color,
+                            1.0,
+                            *composite_operation,
+                            *blend_mode,
+                        )
+                    });
+                }
                 Ok(())
             }
         }
@@ -117,194 +289,757 @@ impl Framebuffer {
 
     /// Paints a line that extends from `p1` to `p2` with the color of `current_color`.
     ///
-    /// Returns: A vector of all the points that should be painted.
-    pub fn line(&mut self, p1: glm::Vec3, p2: glm::Vec3) -> Canvas {
-        let x0 = p1.x;
-        let y0 = p1.y;
+    /// Lines wider than a pixel, according to `stroke_style`, are filled as a rectangle
+    /// centered on the line with the configured caps on both ends. Thinner lines are drawn
+    /// with Xiaolin Wu's algorithm when `anti_aliasing` is enabled,
+    /// otherwise a digital differential analyzer is used.
+    /// Both `p1` and `p2` can lie anywhere inside a pixel, they don't need to be rounded.
+    /// If `stroke_style` has a dash pattern only the dashes are painted.
+    /// The line is clipped to the framebuffer, so only its visible part gets painted.
+    ///
+    /// Returns: A canvas that rasterizes the line as it gets painted or iterated.
+    pub fn line(&mut self, p1: glm::Vec3, p2: glm::Vec3) -> Canvas<'_> {
+        let fragments = self.outline_fragments(vec![p1, p2], false);
 
-        let x1 = p2.x;
-        let y1 = p2.y;
+        Canvas {
+            fragments,
+            owner: self,
+        }
+    }
 
-        let delta_x = (x1 - x0).abs();
-        let delta_y = (y1 - y0).abs();
+    /// Paints the given polygon to the screen.
+    ///
+    /// The polygon is clipped to the framebuffer, so only the edges or parts of edges
+    /// that are visible get painted. Edges are drawn like `line` does, so they follow
+    /// `stroke_style` and are anti aliased when `anti_aliasing` is enabled.
+    /// Corners of wide borders are connected with the configured line join,
+    /// and dash patterns continue around the corners instead of restarting on each edge.
+    pub fn polygon(&mut self, points: Vec<glm::Vec3>) -> Canvas<'_> {
+        let fragments = match points.len() {
+            1 => self.point_fragment(points[0]),
+            _ => self.outline_fragments(points, true),
+        };
 
-        let dir_x = if x0 < x1 { 1.0 } else { -1.0 };
-        let dir_y = if y0 < y1 { 1.0 } else { -1.0 };
+        Canvas {
+            fragments,
+            owner: self,
+        }
+    }
 
-        let mut err = delta_x - delta_y;
+    /// Paints the path that goes through every point in `points`, in order.
+    ///
+    /// Unlike `polygon` the path isn't closed, so there is no edge going back from the last point
+    /// to the first one and wide paths get the configured line caps on both ends.
+    /// Segments are drawn like `line` does, but the vertex shared by two consecutive segments
+    /// is only painted once.
+    pub fn polyline(&mut self, points: Vec<glm::Vec3>) -> Canvas<'_> {
+        let fragments = match points.len() {
+            1 if self.stroke_style.width <= 1.0 => self.point_fragment(points[0]),
+            _ => self.outline_fragments(points, false),
+        };
 
-        let mut current_x = x0;
-        let mut current_y = y0;
+        Canvas {
+            fragments,
+            owner: self,
+        }
+    }
 
-        let mut points = vec![];
+    /// Paints the quadratic Bézier curve going from `start` to `end` bent towards `control`.
+    ///
+    /// The curve is flattened into straight lines no further than `curve_tolerance` from it,
+    /// which are then drawn like `polyline` does.
+    pub fn quadratic_bezier(
+        &mut self,
+        start: glm::Vec3,
+        control: glm::Vec3,
+        end: glm::Vec3,
+    ) -> Canvas<'_> {
+        let points = flatten_quadratic(start, control, end, self.curve_tolerance);
+        self.polyline(points)
+    }
 
-        loop {
-            points.push(Vec3::new(current_x, current_y, 0.0));
+    /// Paints the cubic Bézier curve going from `start` to `end` bent towards
+    /// `control1` and `control2`.
+    ///
+    /// The curve is flattened into straight lines no further than `curve_tolerance` from it,
+    /// which are then drawn like `polyline` does.
+    pub fn cubic_bezier(
+        &mut self,
+        start: glm::Vec3,
+        control1: glm::Vec3,
+        control2: glm::Vec3,
+        end: glm::Vec3,
+    ) -> Canvas<'_> {
+        let points = flatten_cubic(start, control1, control2, end, self.curve_tolerance);
+        self.polyline(points)
+    }
 
-            let reached_x1 = are_equal(current_x, x1, f32::EPSILON);
-            let reached_y1 = are_equal(current_y, y1, f32::EPSILON);
+    /// Paints the outline of the given `path`.
+    ///
+    /// Curves are flattened according to `curve_tolerance`. Closed paths are drawn like
+    /// `polygon` does and open ones like `polyline` does.
+    pub fn path(&mut self, path: &Path) -> Canvas<'_> {
+        let points = path.flatten(self.curve_tolerance);
 
-            if reached_x1 && reached_y1 {
-                break;
-            }
+        if path.closed {
+            self.polygon(points)
+        } else {
+            self.polyline(points)
+        }
+    }
 
-            let e2 = 2.0 * err;
+    /// Paints the outline of the circle centered on `center`, see `ellipse`.
+    pub fn circle(&mut self, center: glm::Vec3, radius: f32) -> Canvas<'_> {
+        self.ellipse(center, radius, radius, 0.0)
+    }
 
-            if e2 > -delta_y {
-                err -= delta_y;
-                current_x += dir_x;
-            }
+    /// Paints the outline of the ellipse centered on `center`.
+    ///
+    /// Ellipses that aren't rotated are drawn with the midpoint ellipse algorithm, which works
+    /// on whole pixels so `center` and both radii are rounded. Rotated ellipses, the ones larger
+    /// than the framebuffer, and the ones whose outline is wide, dashed or anti aliased, keep
+    /// their exact radii and are approximated with a polygon no further than `curve_tolerance`
+    /// from them and drawn like `polygon` does.
+    ///
+    /// * `rotation`: Angle in radians between the x axis and the axis with `radius_x`,
+    ///   clockwise on the screen.
+    pub fn ellipse(
+        &mut self,
+        center: glm::Vec3,
+        radius_x: f32,
+        radius_y: f32,
+        rotation: f32,
+    ) -> Canvas<'_> {
+        let style = &self.stroke_style;
+        let is_hairline = style.width <= 1.0 && !style.is_dashed() && !self.anti_aliasing;
+        let (rounded_x, rounded_y) = (radius_x.round(), radius_y.round());
+        let fits_midpoint =
+            self.is_midpoint_radius(rounded_x) && self.is_midpoint_radius(rounded_y);
 
-            if e2 < delta_x {
-                err += delta_x;
-                current_y += dir_y;
-            }
+        if rotation != 0.0 || !is_hairline || !fits_midpoint {
+            let points =
+                flatten_ellipse(center, radius_x, radius_y, rotation, self.curve_tolerance);
+            return self.polygon(points);
         }
 
+        let center = center.map(f32::round);
+        let bounds = self.pixel_bounds();
+        let fragments = MidpointEllipse::new(rounded_x as i64, rounded_y as i64)
+            .flat_map(move |(x, y)| {
+                let (x, y) = (x as f32, y as f32);
+                // Points on the axes are their own reflection.
+                [
+                    Some((x, y)),
+                    (x != 0.0).then_some((-x, y)),
+                    (y != 0.0).then_some((x, -y)),
+                    (x != 0.0 && y != 0.0).then_some((-x, -y)),
+                ]
+            })
+            .flatten()
+            .map(move |(x, y)| Fragment::solid(center + Vec3::new(x, y, 0.0)))
+            .filter(move |f| bounds.contains(&f.point));
+
         Canvas {
-            data: points,
+            fragments: Box::new(fragments),
             owner: self,
         }
     }
 
-    /// Paints the given polygon to the screen.
-    pub fn polygon(&mut self, mut points: Vec<glm::Vec3>) -> Canvas {
-        let points = match points.len() {
-            1 => vec![points.remove(0)],
-            _ => {
-                let a = points[0];
-                points.push(a);
+    /// Paints an arc of the ellipse centered on `center`.
+    ///
+    /// The arc is approximated with straight lines no further than `curve_tolerance` from it,
+    /// which are then drawn like `polyline` does, so wide arcs get the configured line caps.
+    ///
+    /// * `start_angle`: Angle in radians where the arc starts, measured from the x axis
+    ///   and going clockwise on the screen.
+    /// * `sweep_angle`: Angle in radians covered by the arc, negative values go counterclockwise.
+    pub fn arc(
+        &mut self,
+        center: glm::Vec3,
+        radius_x: f32,
+        radius_y: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+    ) -> Canvas<'_> {
+        let points = flatten_arc(
+            center,
+            radius_x,
+            radius_y,
+            start_angle,
+            sweep_angle,
+            self.curve_tolerance,
+        );
+        self.polyline(points)
+    }
+
+    /// Paints the outline of a pie slice: the arc described like `arc` does,
+    /// closed by two straight lines going through `center`.
+    pub fn pie(
+        &mut self,
+        center: glm::Vec3,
+        radius_x: f32,
+        radius_y: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+    ) -> Canvas<'_> {
+        let points = self.pie_points(center, radius_x, radius_y, start_angle, sweep_angle);
+        self.polygon(points)
+    }
+
+    /// Paints the outline of the part of a ring between `inner_radius` and `outer_radius`
+    /// covered by the angles described like `arc` does.
+    ///
+    /// Segments sweeping a whole turn are outlined as two separate circles.
+    pub fn ring_segment(
+        &mut self,
+        center: glm::Vec3,
+        inner_radius: f32,
+        outer_radius: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+    ) -> Canvas<'_> {
+        if sweep_angle.abs() >= 2.0 * PI {
+            let (outer, inner) = self.full_ring_points(center, inner_radius, outer_radius);
+            let fragments = self
+                .outline_fragments(outer, true)
+                .chain(self.outline_fragments(inner, true));
+
+            return Canvas {
+                fragments: Box::new(fragments),
+                owner: self,
+            };
+        }
+
+        let points =
+            self.ring_segment_points(center, inner_radius, outer_radius, start_angle, sweep_angle);
+        self.polygon(points)
+    }
+
+    /// Computes the vertices of the polygon approximating the given pie slice.
+    fn pie_points(
+        &self,
+        center: glm::Vec3,
+        radius_x: f32,
+        radius_y: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+    ) -> Vec<glm::Vec3> {
+        let mut points = vec![center];
+        points.extend(flatten_arc(
+            center,
+            radius_x,
+            radius_y,
+            start_angle,
+            sweep_angle,
+            self.curve_tolerance,
+        ));
+
+        points
+    }
+
+    /// Computes the polygons approximating the outer and the inner circle of a full ring.
+    fn full_ring_points(
+        &self,
+        center: glm::Vec3,
+        inner_radius: f32,
+        outer_radius: f32,
+    ) -> (Vec<glm::Vec3>, Vec<glm::Vec3>) {
+        let circle = |radius| flatten_ellipse(center, radius, radius, 0.0, self.curve_tolerance);
+
+        (circle(outer_radius), circle(inner_radius))
+    }
+
+    /// Computes the vertices of the polygon approximating the given ring segment.
+    ///
+    /// The outer arc goes one way and the inner arc comes back the other way.
+    fn ring_segment_points(
+        &self,
+        center: glm::Vec3,
+        inner_radius: f32,
+        outer_radius: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+    ) -> Vec<glm::Vec3> {
+        let mut points = flatten_arc(
+            center,
+            outer_radius,
+            outer_radius,
+            start_angle,
+            sweep_angle,
+            self.curve_tolerance,
+        );
+        points.extend(
+            flatten_arc(
+                center,
+                inner_radius,
+                inner_radius,
+                start_angle,
+                sweep_angle,
+                self.curve_tolerance,
+            )
+            .into_iter()
+            .rev(),
+        );
+
+        points
+    }
+
+    /// Computes the fragment of a single `point`, which is left out when it's outside
+    /// of the framebuffer.
+    fn point_fragment(&self, point: glm::Vec3) -> Box<dyn Iterator<Item = Fragment>> {
+        let is_visible = self.pixel_bounds().contains(&point);
+
+        Box::new(is_visible.then(|| Fragment::solid(point)).into_iter())
+    }
+
+    /// Computes the fragments of the outline of the path formed by `points`
+    /// following the `stroke_style`.
+    ///
+    /// Hairlines are rasterized lazily, as the fragments are consumed. Wide outlines are
+    /// scan converted up front, see `stroke_fragments`.
+    ///
+    /// * `closed`: Whether the path goes back from the last point to the first one.
+    fn outline_fragments(
+        &self,
+        points: Vec<glm::Vec3>,
+        closed: bool,
+    ) -> Box<dyn Iterator<Item = Fragment>> {
+        let style = &self.stroke_style;
+        let dashes = style.is_dashed().then(|| {
+            // Dashes can't be seen once they are further from the framebuffer than
+            // the tips of their miter joins or the corners of their square caps.
+            let reach = style.width.max(1.0) / 2.0 * style.miter_limit.max(SQRT_2);
+            let bounds = self.fill_bounds().expand(reach);
+
+            dash_path(
+                &points,
+                closed,
+                &style.dash_array,
+                style.dash_offset,
+                &bounds,
+            )
+        });
+
+        let segments: Vec<(glm::Vec3, glm::Vec3)> = match (style.width > 1.0, dashes) {
+            (true, None) => return self.stroke_fragments(stroke_outlines(&points, closed, style)),
+            (true, Some(dashes)) => {
+                return self.stroke_fragments(
+                    dashes
+                        .iter()
+                        .flat_map(|dash| stroke_outlines(dash, false, style))
+                        .collect(),
+                )
+            }
+            (false, None) if closed => {
+                let clipped = clip_polygon(&points, &self.pixel_bounds());
 
-                points
-                    .windows(2)
-                    .flat_map(|ps| self.line(ps[0], ps[1]).data)
+                clipped
+                    .iter()
+                    .zip(clipped.iter().cycle().skip(1))
+                    .filter(|(start, _)| start.on_outline)
+                    .map(|(start, end)| (start.point, end.point))
                     .collect()
             }
+            (false, None) => points.windows(2).map(|ps| (ps[0], ps[1])).collect(),
+            (false, Some(dashes)) => dashes
+                .iter()
+                .flat_map(|dash| dash.windows(2))
+                .map(|ps| (ps[0], ps[1]))
+                .collect(),
         };
 
-        Canvas {
-            data: points,
-            owner: self,
-        }
+        let bounds = self.pixel_bounds();
+        let anti_aliasing = self.anti_aliasing;
+
+        Box::new(MergeNearby::new(
+            segments
+                .into_iter()
+                .flat_map(move |(start, end)| hairline(start, end, &bounds, anti_aliasing)),
+            closed,
+        ))
     }
 
     /// Paints the given polygon to the screen, filled with the given color.
     ///
-    /// Returns a tuple containing the border and filled polygon canvases.
+    /// The interior is computed with a scanline fill over the edges of the polygon,
+    /// so both convex and concave polygons are filled correctly.
+    /// Self intersecting polygons are filled according to `fill_rule`.
+    /// When `anti_aliasing` is enabled the pixels on the edges are blended with
+    /// the previous color according to how much of them is inside the polygon.
+    /// The border is painted on top of the interior using `border_color`.
     pub fn paint_filled_polygon(
         &mut self,
-        mut points: Vec<glm::Vec3>,
+        points: Vec<glm::Vec3>,
         fill_color: impl Into<Color>,
         border_color: impl Into<Color>,
     ) -> Result<(), PaintPointErrors> {
-        let rounded_corners = points
-            .iter()
-            .map(|a| (a.x.round() as u32, a.y.round() as u32));
-        let top_row = rounded_corners.clone().map(|x| x.1).min().unwrap();
-        let bottom_row = rounded_corners.clone().map(|x| x.1).max().unwrap();
-
-        let left_col = rounded_corners.clone().map(|x| x.0).min().unwrap();
-        let right_col = rounded_corners.map(|x| x.0).max().unwrap();
+        self.paint_filled_polygon_with_holes(points, vec![], fill_color, border_color)
+    }
 
-        let area_width = right_col - left_col;
+    /// Paints the polygon formed by `outer` to the screen, filled with the given color.
+    ///
+    /// The area inside each ring of `holes` is left untouched.
+    /// The borders of the outer ring and every hole are painted using `border_color`.
+    /// Every ring is clipped to the framebuffer, so only the visible part of the polygon is painted.
+    pub fn paint_filled_polygon_with_holes(
+        &mut self,
+        outer: Vec<glm::Vec3>,
+        holes: Vec<Vec<glm::Vec3>>,
+        fill_color: impl Into<Color>,
+        border_color: impl Into<Color>,
+    ) -> Result<(), PaintPointErrors> {
+        self.set_current_color(fill_color);
+        self.fill_polygon_with_holes(&outer, &holes)?;
 
-        let previous_colors: Vec<(u32, u32, Color)> = (top_row..=bottom_row)
-            .flat_map(|r| {
-                (left_col..=right_col)
-                    .map(|c| (c, r, self.get_color(c as usize, r as usize).unwrap()))
-                    .collect::<Vec<(u32, u32, Color)>>()
-            })
-            .collect();
+        self.set_current_color(border_color);
+        self.polygon(outer).paint()?;
+        holes
+            .into_iter()
+            .try_for_each(|hole| self.polygon(hole).paint())
+    }
 
-        // Fill the area with the fill color
+    /// Paints the ellipse centered on `center` to the screen, filled with the given color.
+    ///
+    /// The interior is filled like `fill_ellipse` does and the border is painted
+    /// on top of it like `ellipse` does, using `border_color`.
+    pub fn paint_filled_ellipse(
+        &mut self,
+        center: glm::Vec3,
+        radius_x: f32,
+        radius_y: f32,
+        rotation: f32,
+        fill_color: impl Into<Color>,
+        border_color: impl Into<Color>,
+    ) -> Result<(), PaintPointErrors> {
         self.set_current_color(fill_color);
-        previous_colors.iter().try_for_each(|(x, y, _)| {
-            self.paint_point(glm::Vec3::new(*x as f32, *y as f32, 0.0))
-        })?;
+        self.fill_ellipse(center, radius_x, radius_y, rotation)?;
 
-        // Define border points
-        let points = match points.len() {
-            1 => vec![points.remove(0)],
-            _ => {
-                let a = points[0];
-                points.push(a);
+        self.set_current_color(border_color);
+        self.ellipse(center, radius_x, radius_y, rotation).paint()
+    }
 
-                points
-                    .windows(2)
-                    .flat_map(|ps| self.line(ps[0], ps[1]).data)
-                    .collect()
-            }
-        };
+    /// Fills the circle centered on `center`, see `fill_ellipse`.
+    pub fn fill_circle(&mut self, center: glm::Vec3, radius: f32) -> Result<(), PaintPointErrors> {
+        self.fill_ellipse(center, radius, radius, 0.0)
+    }
+
+    /// Fills the ellipse centered on `center` with the color of `current_color`,
+    /// without painting its border.
+    ///
+    /// Ellipses that aren't rotated are filled with the midpoint ellipse algorithm, rounding
+    /// `center` and both radii like `ellipse` does, so each row reaches right up to the pixels
+    /// of the outline. Rotated ellipses paint the pixels whose center lies inside them, which is
+    /// computed exactly on each scanline. When `anti_aliasing` is enabled the ellipse is
+    /// approximated with a polygon no further than `curve_tolerance` from it and filled like
+    /// `fill_polygon` does.
+    ///
+    /// * `rotation`: Angle in radians between the x axis and the axis with `radius_x`,
+    ///   clockwise on the screen.
+    pub fn fill_ellipse(
+        &mut self,
+        center: glm::Vec3,
+        radius_x: f32,
+        radius_y: f32,
+        rotation: f32,
+    ) -> Result<(), PaintPointErrors> {
+        if radius_x <= 0.0 || radius_y <= 0.0 {
+            return Ok(());
+        }
+
+        if self.anti_aliasing {
+            let points =
+                flatten_ellipse(center, radius_x, radius_y, rotation, self.curve_tolerance);
+            return self.fill_polygon(&points);
+        }
+
+        let bounds = self.fill_bounds();
+        let (rounded_x, rounded_y) = (radius_x.round(), radius_y.round());
+        if rotation == 0.0
+            && self.is_midpoint_radius(rounded_x)
+            && self.is_midpoint_radius(rounded_y)
+        {
+            let center = center.map(f32::round);
+            return midpoint_ellipse_spans(center, rounded_x as i64, rounded_y as i64, &bounds)
+                .try_for_each(|span| self.paint_span(span));
+        }
+
+        ellipse_spans(center, radius_x, radius_y, rotation, &bounds)
+            .try_for_each(|span| self.paint_span(span))
+    }
+
+    /// Whether a rounded radius can be drawn with the midpoint ellipse algorithm.
+    ///
+    /// Its work grows with the radius rather than with the visible part of the ellipse,
+    /// so radii larger than the framebuffer are left to the polygon approximation instead.
+    fn is_midpoint_radius(&self, radius: f32) -> bool {
+        (1.0..=(self.width + self.height) as f32).contains(&radius)
+    }
+
+    /// Fills the pie slice described like `pie` does with the color of `current_color`,
+    /// without painting its border.
+    ///
+    /// The slice is approximated with a polygon no further than `curve_tolerance` from it
+    /// and filled like `fill_polygon` does.
+    pub fn fill_pie(
+        &mut self,
+        center: glm::Vec3,
+        radius_x: f32,
+        radius_y: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+    ) -> Result<(), PaintPointErrors> {
+        let points = self.pie_points(center, radius_x, radius_y, start_angle, sweep_angle);
+        self.fill_polygon(&points)
+    }
+
+    /// Fills the ring segment described like `ring_segment` does with the color of
+    /// `current_color`, without painting its border.
+    ///
+    /// The segment is approximated with a polygon no further than `curve_tolerance` from it
+    /// and filled like `fill_polygon` does. Segments sweeping a whole turn are filled like
+    /// `fill_polygon_with_holes` does, with the inner circle as the hole.
+    pub fn fill_ring_segment(
+        &mut self,
+        center: glm::Vec3,
+        inner_radius: f32,
+        outer_radius: f32,
+        start_angle: f32,
+        sweep_angle: f32,
+    ) -> Result<(), PaintPointErrors> {
+        if sweep_angle.abs() >= 2.0 * PI {
+            let (outer, inner) = self.full_ring_points(center, inner_radius, outer_radius);
+            return self.fill_polygon_with_holes(&outer, &[inner]);
+        }
+
+        let points =
+            self.ring_segment_points(center, inner_radius, outer_radius, start_angle, sweep_angle);
+        self.fill_polygon(&points)
+    }
 
-        let rounded_border: Vec<(u32, u32)> = points
+    /// Fills the given polygon with the color of `current_color`, without painting its border.
+    ///
+    /// Pixels are painted when their center lies inside the polygon. Centers lying exactly on
+    /// an edge follow the top-left rule: they are only painted if the edge is on the top or the
+    /// left of the polygon. That way polygons sharing an edge tile with no gaps and no overlap.
+    pub fn fill_polygon(&mut self, points: &[glm::Vec3]) -> Result<(), PaintPointErrors> {
+        self.fill_polygon_with_holes(points, &[])
+    }
+
+    /// Fills the area enclosed by `path` with the color of `current_color`,
+    /// like `fill_polygon` does.
+    ///
+    /// The path is always treated as closed. Curves are flattened according to `curve_tolerance`.
+    pub fn fill_path(&mut self, path: &Path) -> Result<(), PaintPointErrors> {
+        self.fill_path_with_holes(path, &[])
+    }
+
+    /// Fills the area enclosed by `outer` with the color of `current_color`,
+    /// leaving the area enclosed by each path of `holes` untouched.
+    pub fn fill_path_with_holes(
+        &mut self,
+        outer: &Path,
+        holes: &[Path],
+    ) -> Result<(), PaintPointErrors> {
+        let holes: Vec<Vec<glm::Vec3>> = holes
             .iter()
-            .map(|a| (a.x.round() as u32, a.y.round() as u32))
+            .map(|hole| hole.flatten(self.curve_tolerance))
             .collect();
 
-        // Scan until border from all directions
-        // Top down scanning...
-        for col in left_col..=(right_col) {
-            for row in top_row..=(bottom_row) {
-                let color_cords = ((row - top_row) * (area_width + 1) + (col - left_col)) as usize;
-                let (x, y, color) = previous_colors.get(color_cords).unwrap();
+        self.fill_polygon_with_holes(&outer.flatten(self.curve_tolerance), &holes)
+    }
 
-                if rounded_border.contains(&(*x, *y)) {
-                    break;
-                }
-                self.set_current_color(*color);
-                self.paint_point(glm::Vec3::new(*x as f32, *y as f32, 0.0))?;
-            }
+    /// Fills the polygon formed by `outer` with the color of `current_color`,
+    /// leaving the area inside each ring of `holes` untouched.
+    ///
+    /// The border of the polygon isn't painted, see `fill_polygon` for how edges are handled.
+    pub fn fill_polygon_with_holes(
+        &mut self,
+        outer: &[glm::Vec3],
+        holes: &[Vec<glm::Vec3>],
+    ) -> Result<(), PaintPointErrors> {
+        let bounds = self.fill_bounds();
+        let clipped_holes: Vec<Vec<glm::Vec3>> =
+            holes.iter().map(|hole| clip_ring(hole, &bounds)).collect();
+        let edge_table = EdgeTable::with_holes(&clip_ring(outer, &bounds), &clipped_holes);
+
+        if self.anti_aliasing {
+            edge_table
+                .coverage(self.fill_rule, ANTI_ALIASING_SAMPLES)
+                .into_iter()
+                .try_for_each(|row| {
+                    row.coverage
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, coverage)| **coverage > 0.0)
+                        .try_for_each(|(i, coverage)| {
+                            let point = Vec3::new(row.x_start + i as f32, row.y, 0.0);
+                            self.blend_point(point, *coverage)
+                        })
+                })
+        } else {
+            edge_table
+                .spans(self.fill_rule)
+                .into_iter()
+                .try_for_each(|span| self.paint_span(span))
         }
+    }
 
-        // Bottom up scanning
-        for col in left_col..=(right_col) {
-            for row in (top_row..=(bottom_row)).rev() {
-                let color_cords = ((row - top_row) * (area_width + 1) + (col - left_col)) as usize;
-                let (x, y, color) = previous_colors.get(color_cords).unwrap();
+    /// Computes the fragments covered by the union of the given stroke outlines.
+    ///
+    /// The outlines are clipped to the framebuffer and anti aliased when `anti_aliasing` is enabled.
+    /// Every span, or every row of coverage when anti aliasing, is computed before returning,
+    /// so only turning them into fragments is left for when they are consumed.
+    fn stroke_fragments(
+        &self,
+        outlines: Vec<Vec<glm::Vec3>>,
+    ) -> Box<dyn Iterator<Item = Fragment>> {
+        let bounds = self.fill_bounds();
+        let clipped: Vec<Vec<glm::Vec3>> = outlines
+            .iter()
+            .map(|outline| clip_ring(outline, &bounds))
+            .collect();
+        let edge_table = EdgeTable::from_rings(&clipped);
 
-                if rounded_border.contains(&(*x, *y)) {
-                    break;
-                }
-                self.set_current_color(*color);
-                self.paint_point(glm::Vec3::new(*x as f32, *y as f32, 0.0))?;
-            }
+        if self.anti_aliasing {
+            Box::new(
+                edge_table
+                    .coverage(FillRule::NonZero, ANTI_ALIASING_SAMPLES)
+                    .into_iter()
+                    .flat_map(|row| {
+                        row.coverage
+                            .into_iter()
+                            .enumerate()
+                            .filter(|(_, coverage)| *coverage > 0.0)
+                            .map(move |(i, coverage)| Fragment {
+                                point: Vec3::new(row.x_start + i as f32, row.y, 0.0),
+                                coverage,
+                            })
+                    }),
+            )
+        } else {
+            Box::new(
+                edge_table
+                    .spans(FillRule::NonZero)
+                    .into_iter()
+                    .flat_map(|span| {
+                        span.pixels()
+                            .map(move |x| Fragment::solid(Vec3::new(x as f32, span.y, 0.0)))
+                    }),
+            )
+        }
+    }
+
+    /// Paints a filled triangle whose color is interpolated from the color of its vertices.
+    ///
+    /// Each pixel whose center lies inside the triangle is painted with the colors of the
+    /// vertices weighted by the barycentric coordinates of its center.
+    /// Centers lying exactly on an edge follow the same top-left rule as `fill_polygon`.
+    ///
+    /// * `vertices`: The corners of the triangle, in any order.
+    /// * `colors`: The color of each corner of the triangle.
+    pub fn triangle(
+        &mut self,
+        vertices: [glm::Vec3; 3],
+        colors: [Color; 3],
+    ) -> Result<(), PaintPointErrors> {
+        let [a, b, c] = vertices;
+        let area = edge_function(&a, &b, &c);
+        if area == 0.0 {
+            return Ok(());
         }
 
-        // Left right scanning
-        for row in top_row..=bottom_row {
-            for col in left_col..=right_col {
-                let color_cords = ((row - top_row) * (area_width + 1) + (col - left_col)) as usize;
-                let (x, y, color) = previous_colors.get(color_cords).unwrap();
+        // With a positive area the vertices go clockwise on the screen.
+        let (b, c, colors) = if area > 0.0 {
+            (b, c, colors)
+        } else {
+            (c, b, [colors[0], colors[2], colors[1]])
+        };
+        let area = area.abs();
+        let edge_bias = |start: &glm::Vec3, end: &glm::Vec3| {
+            let is_top = start.y == end.y && start.x < end.x;
+            let is_left = end.y < start.y;
 
-                if rounded_border.contains(&(*x, *y)) {
-                    break;
-                }
-                self.set_current_color(*color);
-                self.paint_point(glm::Vec3::new(*x as f32, *y as f32, 0.0))?;
+            if is_top || is_left {
+                0.0
+            } else {
+                f32::MIN_POSITIVE
             }
-        }
+        };
+        let bias_a = edge_bias(&b, &c);
+        let bias_b = edge_bias(&c, &a);
+        let bias_c = edge_bias(&a, &b);
 
-        // Right to left scanning
-        for row in top_row..=bottom_row {
-            for col in (left_col..=right_col).rev() {
-                let color_cords = ((row - top_row) * (area_width + 1) + (col - left_col)) as usize;
-                let (x, y, color) = previous_colors.get(color_cords).unwrap();
+        let bounds = self.pixel_bounds();
+        let top = a.y.min(b.y).min(c.y).max(bounds.min_y).ceil() as i64;
+        let bottom = a.y.max(b.y).max(c.y).min(bounds.max_y).floor() as i64;
+        let left = a.x.min(b.x).min(c.x).max(bounds.min_x).ceil() as i64;
+        let right = a.x.max(b.x).max(c.x).min(bounds.max_x).floor() as i64;
 
-                if rounded_border.contains(&(*x, *y)) {
-                    break;
+        for y in top..=bottom {
+            for x in left..=right {
+                let point = Vec3::new(x as f32, y as f32, 0.0);
+                let e_a = edge_function(&b, &c, &point);
+                let e_b = edge_function(&c, &a, &point);
+                let e_c = edge_function(&a, &b, &point);
+
+                if e_a < bias_a || e_b < bias_b || e_c < bias_c {
+                    continue;
                 }
-                self.set_current_color(*color);
-                self.paint_point(glm::Vec3::new(*x as f32, *y as f32, 0.0))?;
+
+                let w_a = e_a / area;
+                let w_b = e_b / area;
+                let w_c = e_c / area;
+
+                // Mixed in floating point and rounded once, so a single color comes out exact.
+                let channel = |channel: fn(&Color) -> u8| {
+                    (channel(&colors[0]) as f32 * w_a
+                        + channel(&colors[1]) as f32 * w_b
+                        + channel(&colors[2]) as f32 * w_c)
+                        .round()
+                        .clamp(0.0, 255.0) as u8
+                };
+                let color = Color::new_rgba(
+                    channel(|c| c.r),
+                    channel(|c| c.g),
+                    channel(|c| c.b),
+                    channel(|c| c.a),
+                );
+
+                let index = self.point_index(point)?;
+                self.buffer[index] = composite(
+                    self.buffer[index],
+                    color,
+                    1.0,
+                    self.composite_operation,
+                    self.blend_mode,
+                );
             }
         }
 
-        self.set_current_color(border_color);
+        Ok(())
+    }
 
-        Canvas {
-            data: points,
-            owner: self,
+    /// The rectangle containing the center of every pixel of the buffer.
+    fn pixel_bounds(&self) -> Rect {
+        Rect {
+            min_x: 0.0,
+            min_y: 0.0,
+            max_x: self.width as f32 - 1.0,
+            max_y: self.height as f32 - 1.0,
+        }
+    }
+
+    /// The rectangle covered by the pixels of the buffer.
+    ///
+    /// Since pixels are centered on integer coordinates, it extends half a pixel
+    /// past the centers of the pixels on the sides.
+    fn fill_bounds(&self) -> Rect {
+        Rect {
+            min_x: -0.5,
+            min_y: -0.5,
+            max_x: self.width as f32 - 0.5,
+            max_y: self.height as f32 - 0.5,
         }
-        .paint()
     }
 
     /// Gets the color of a point in the buffer.
@@ -316,46 +1051,1299 @@ impl Framebuffer {
             ..
         } = self;
 
-        match (x <= *width, y <= *height) {
+        match (x < *width, y < *height) {
             (_, false) => Err(GetColorErrors::YTooLarge),
             (false, _) => Err(GetColorErrors::XTooLarge),
-            _ => Ok(buffer[y * *width + x].into()),
+            _ => Ok(Color::from_argb(buffer[y * *width + x])),
         }
     }
 
-    /// Sets the `background_color` property.
-    /// This method should also regenerate the `empty_buffer`.
+    /// Recolors the region of pixels connected to (`x`, `y`) that share its color.
     ///
-    /// * `new_color`: The color to apply.
-    pub fn set_background_color(&mut self, new_color: impl Into<Color>) {
-        let Framebuffer {
-            width,
-            height,
-            background_color,
-            empty_buffer,
-            ..
-        } = self;
+    /// The region is explored iteratively so big regions can't overflow the stack.
+    ///
+    /// * `color`: The color to apply to the region.
+    /// * `connectivity`: Which neighbours of a pixel belong to its region.
+    pub fn flood_fill(
+        &mut self,
+        x: usize,
+        y: usize,
+        color: impl Into<Color>,
+        connectivity: Connectivity,
+    ) -> Result<(), PaintPointErrors> {
+        self.check_bounds(x, y)?;
+        let target = self.buffer[y * self.width + x];
 
-        *background_color = new_color.into();
-        *empty_buffer = create_filled_buffer(width, height, background_color);
+        self.fill_region(x, y, color.into(), connectivity, |pixel| pixel == target);
+        Ok(())
     }
 
-    /// Sets the `current_color` property.
+    /// Recolors the region of pixels connected to (`x`, `y`) until reaching the `boundary` color.
     ///
-    /// * `new_color`: The color to apply.
-    pub fn set_current_color(&mut self, new_color: impl Into<Color>) {
-        self.current_color = new_color.into();
+    /// The region is explored iteratively so big regions can't overflow the stack.
+    ///
+    /// * `fill`: The color to apply to the region.
+    /// * `boundary`: The color of the pixels that enclose the region.
+    /// * `connectivity`: Which neighbours of a pixel belong to its region.
+    pub fn boundary_fill(
+        &mut self,
+        x: usize,
+        y: usize,
+        fill: impl Into<Color>,
+        boundary: impl Into<Color>,
+        connectivity: Connectivity,
+    ) -> Result<(), PaintPointErrors> {
+        self.check_bounds(x, y)?;
+        let boundary = boundary.into().to_argb();
+
+        self.fill_region(x, y, fill.into(), connectivity, |pixel| pixel != boundary);
+        Ok(())
     }
 
-    /// Saves the pixel data into a .bmp located in the given `file_path`.
-    pub fn save(&self, file_path: &str) -> std::io::Result<()> {
+    /// Paints every pixel reachable from (`x`, `y`) through pixels for which `belongs` is true.
+    fn fill_region(
+        &mut self,
+        x: usize,
+        y: usize,
+        color: Color,
+        connectivity: Connectivity,
+        belongs: impl Fn(u32) -> bool,
+    ) {
         let Framebuffer {
             width,
             height,
             buffer,
+            composite_operation,
+            blend_mode,
             ..
         } = self;
+        let mut visited = vec![false; *width * *height];
+        let mut pending = vec![(x, y)];
 
-        write_bmp_file(file_path, buffer, *width, *height)
+        while let Some((x, y)) = pending.pop() {
+            let index = y * *width + x;
+            if visited[index] || !belongs(buffer[index]) {
+                continue;
+            }
+
+            visited[index] = true;
+            buffer[index] = composite(buffer[index], color, 1.0, *composite_operation, *blend_mode);
+
+            pending.extend(connectivity.offsets().iter().filter_map(|(dx, dy)| {
+                let x = x.checked_add_signed(*dx).filter(|x| x < width)?;
+                let y = y.checked_add_signed(*dy).filter(|y| y < height)?;
+                Some((x, y))
+            }));
+        }
+    }
+
+    /// Checks that (`x`, `y`) is a pixel of the buffer.
+    fn check_bounds(&self, x: usize, y: usize) -> Result<(), PaintPointErrors> {
+        match (x < self.width, y < self.height) {
+            (false, _) => Err(PaintPointErrors::XTooLarge),
+            (_, false) => Err(PaintPointErrors::YTooLarge),
+            _ => Ok(()),
+        }
+    }
+
+    /// Sets the `background_color` property.
+    /// This method should also regenerate the `empty_buffer`.
+    ///
+    /// * `new_color`: The color to apply.
+    pub fn set_background_color(&mut self, new_color: impl Into<Color>) {
+        let Framebuffer {
+            width,
+            height,
+            background_color,
+            empty_buffer,
+            ..
+        } = self;
+
+        *background_color = new_color.into();
+        *empty_buffer = create_filled_buffer(width, height, background_color);
+    }
+
+    /// Sets the `current_color` property.
+    ///
+    /// * `new_color`: The color to apply. Translucent colors are blended on top of
+    ///   what was already painted.
+    pub fn set_current_color(&mut self, new_color: impl Into<Color>) {
+        self.current_color = new_color.into();
+    }
+
+    /// Sets the `fill_rule` property.
+    ///
+    /// * `new_rule`: The rule used to decide the interior of filled polygons.
+    pub fn set_fill_rule(&mut self, new_rule: FillRule) {
+        self.fill_rule = new_rule;
+    }
+
+    /// Sets the width of the `stroke_style`.
+    ///
+    /// * `new_width`: The width in pixels of lines and polygon borders.
+    pub fn set_line_width(&mut self, new_width: f32) {
+        self.stroke_style.width = new_width;
+    }
+
+    /// Sets the join of the `stroke_style`.
+    ///
+    /// * `new_join`: The shape used on the corners of wide polygon borders.
+    pub fn set_line_join(&mut self, new_join: LineJoin) {
+        self.stroke_style.join = new_join;
+    }
+
+    /// Sets the cap of the `stroke_style`.
+    ///
+    /// * `new_cap`: The shape used on the ends of wide lines.
+    pub fn set_line_cap(&mut self, new_cap: LineCap) {
+        self.stroke_style.cap = new_cap;
+    }
+
+    /// Sets the dash pattern of the `stroke_style`.
+    ///
+    /// * `dash_array`: The lengths of the alternating dashes and gaps, in pixels.
+    ///   An empty array draws solid lines.
+    pub fn set_line_dash(&mut self, dash_array: Vec<f32>) {
+        self.stroke_style.dash_array = dash_array;
+    }
+
+    /// Sets the dash offset of the `stroke_style`.
+    ///
+    /// * `new_offset`: How far into the dash pattern lines start, in pixels.
+    pub fn set_line_dash_offset(&mut self, new_offset: f32) {
+        self.stroke_style.dash_offset = new_offset;
+    }
+
+    /// Sets the miter limit of the `stroke_style`.
+    ///
+    /// * `new_limit`: The maximum ratio between the length of a miter join and the line width,
+    ///   sharper corners are beveled instead.
+    pub fn set_miter_limit(&mut self, new_limit: f32) {
+        self.stroke_style.miter_limit = new_limit;
+    }
+
+    /// Sets the `composite_operation` property.
+    ///
+    /// * `new_operation`: The Porter-Duff operator used to combine what gets painted
+    ///   with what was already there, by every way of painting.
+    pub fn set_composite_operation(&mut self, new_operation: CompositeOperation) {
+        self.composite_operation = new_operation;
+    }
+
+    /// Sets the `blend_mode` property.
+    ///
+    /// * `new_mode`: How the colors being painted are mixed with the ones already there
+    ///   before being composited with `composite_operation`.
+    pub fn set_blend_mode(&mut self, new_mode: BlendMode) {
+        self.blend_mode = new_mode;
+    }
+
+    /// Sets the `curve_tolerance` property.
+    ///
+    /// * `new_tolerance`: The maximum distance in pixels between a Bézier curve and
+    ///   the straight lines it's drawn with. Smaller tolerances give smoother curves.
+    pub fn set_curve_tolerance(&mut self, new_tolerance: f32) {
+        self.curve_tolerance = new_tolerance;
+    }
+
+    /// Sets the `anti_aliasing` property.
+    ///
+    /// * `enabled`: Whether lines and the edges of filled polygons should be smoothed.
+    pub fn set_anti_aliasing(&mut self, enabled: bool) {
+        self.anti_aliasing = enabled;
+    }
+
+    /// Saves the pixel data into a .bmp located in the given `file_path`.
+    pub fn save(&self, file_path: &str) -> std::io::Result<()> {
+        let Framebuffer {
+            width,
+            height,
+            buffer,
+            ..
+        } = self;
+
+        write_bmp_file(file_path, buffer, *width, *height)
+    }
+}
+
+/// Combines `color` with the pixel `destination`, first mixing them according to `blend_mode`
+/// and then compositing the result with the given `operation`.
+///
+/// Pixels are stored as 0xAARRGGBB, so their alpha is kept even when it's 0.
+///
+/// * `coverage`: Fraction of the pixel covered, between 0 and 1.
+fn composite(
+    destination: u32,
+    color: Color,
+    coverage: f32,
+    operation: CompositeOperation,
+    blend_mode: BlendMode,
+) -> u32 {
+    let destination = Color::from_argb(destination);
+    let color = blend_mode.blend(color, destination);
+
+    operation.composite(color, destination, coverage).to_argb()
+}
+
+/// Amount of fragments `MergeNearby` keeps around waiting for others on the same pixel.
+const MERGE_WINDOW: usize = 4;
+
+/// Lazily combines fragments that fall on the same pixel and are close to each other
+/// in the sequence, like the ones around the vertex shared by two consecutive segments.
+///
+/// The coverage of the combined fragment is the sum of the coverages, up to a fully covered pixel.
+struct MergeNearby<I> {
+    fragments: I,
+    /// Fragments that may still be combined with the upcoming ones.
+    window: VecDeque<Fragment>,
+    /// First fragments of a closed path, kept until the end so they can be
+    /// combined with the last ones.
+    head: Vec<Fragment>,
+    head_len: usize,
+}
+
+impl<I: Iterator<Item = Fragment>> MergeNearby<I> {
+    /// * `closed`: Whether the fragments trace a closed path that ends where it started.
+    fn new(fragments: I, closed: bool) -> Self {
+        MergeNearby {
+            fragments,
+            window: VecDeque::with_capacity(MERGE_WINDOW + 1),
+            head: Vec::with_capacity(MERGE_WINDOW),
+            head_len: if closed { MERGE_WINDOW } else { 0 },
+        }
+    }
+}
+
+impl<I: Iterator<Item = Fragment>> Iterator for MergeNearby<I> {
+    type Item = Fragment;
+
+    fn next(&mut self) -> Option<Fragment> {
+        for fragment in self.fragments.by_ref() {
+            let pixel = fragment.point.map(f32::round);
+            let same_pixel = self
+                .head
+                .iter_mut()
+                .chain(self.window.iter_mut())
+                .find(|f| f.point.map(f32::round) == pixel);
+
+            if let Some(merged) = same_pixel {
+                merged.coverage = (merged.coverage + fragment.coverage).min(1.0);
+            } else if self.head.len() < self.head_len {
+                self.head.push(fragment);
+            } else {
+                self.window.push_back(fragment);
+                if self.window.len() > MERGE_WINDOW {
+                    return self.window.pop_front();
+                }
+            }
+        }
+
+        self.window.pop_front().or_else(|| self.head.pop())
+    }
+}
+
+/// Clips the closed ring formed by `points` to the given `bounds`.
+fn clip_ring(points: &[glm::Vec3], bounds: &Rect) -> Vec<glm::Vec3> {
+    clip_polygon(points, bounds)
+        .into_iter()
+        .map(|vertex| vertex.point)
+        .collect()
+}
+
+/// Computes the spans of the pixels whose centers are inside the given ellipse,
+/// clipped to `bounds`. Both radii must be positive.
+///
+/// Each scanline crosses the ellipse where the equation of the ellipse,
+/// written in the coordinates of the framebuffer, has its roots.
+fn ellipse_spans(
+    center: glm::Vec3,
+    radius_x: f32,
+    radius_y: f32,
+    rotation: f32,
+    bounds: &Rect,
+) -> impl Iterator<Item = Span> {
+    let (sin, cos) = rotation.sin_cos();
+    let (rx2, ry2) = (radius_x * radius_x, radius_y * radius_y);
+
+    // The ellipse is every point with `a * dx^2 + b * dx * dy + c * dy^2 <= 1`,
+    // where `dx` and `dy` are measured from the center.
+    let a = cos * cos / rx2 + sin * sin / ry2;
+    let b = 2.0 * sin * cos * (1.0 / rx2 - 1.0 / ry2);
+    let c = sin * sin / rx2 + cos * cos / ry2;
+
+    let half_height = (rx2 * sin * sin + ry2 * cos * cos).sqrt();
+    let first_row = (center.y - half_height).max(bounds.min_y).ceil() as i64;
+    let last_row = (center.y + half_height).min(bounds.max_y).floor() as i64;
+    let (min_x, max_x) = (bounds.min_x, bounds.max_x);
+
+    (first_row..=last_row).filter_map(move |y| {
+        let dy = (y as f32) - center.y;
+        let discriminant = (b * dy).powi(2) - 4.0 * a * (c * dy * dy - 1.0);
+        if discriminant < 0.0 {
+            return None;
+        }
+
+        let root = discriminant.sqrt();
+        Some(Span {
+            y: y as f32,
+            x_start: (center.x + (-b * dy - root) / (2.0 * a)).max(min_x),
+            x_end: (center.x + (-b * dy + root) / (2.0 * a)).min(max_x),
+        })
+    })
+}
+
+/// Computes the spans of the pixels inside the outline drawn by the midpoint ellipse
+/// algorithm for the given ellipse, clipped to `bounds`. Both radii must be positive.
+///
+/// Each row goes from the outermost pixel of the outline on one side
+/// to the outermost one on the other, without including them.
+fn midpoint_ellipse_spans(
+    center: glm::Vec3,
+    radius_x: i64,
+    radius_y: i64,
+    bounds: &Rect,
+) -> impl Iterator<Item = Span> {
+    let first_row = (center.y - radius_y as f32).max(bounds.min_y).ceil() as i64;
+    let last_row = (center.y + radius_y as f32).min(bounds.max_y).floor() as i64;
+    let (min_x, max_x) = (bounds.min_x, bounds.max_x);
+
+    // Only the rows between these distances from the center are visible.
+    let distance = move |row: i64| (row as f32 - center.y).abs() as i64;
+    let (nearest, farthest) = if first_row > last_row {
+        (1, 0)
+    } else if (first_row as f32..=last_row as f32).contains(&center.y) {
+        (0, distance(first_row).max(distance(last_row)))
+    } else {
+        let (first, last) = (distance(first_row), distance(last_row));
+        (first.min(last), first.max(last))
+    };
+
+    // The points go down the quarter with an increasing x, so the last one of each row
+    // is its outermost.
+    let mut extents = vec![0; (farthest + 1 - nearest).max(0) as usize];
+    for (x, y) in MidpointEllipse::new(radius_x, radius_y)
+        .take_while(|&(_, y)| y >= nearest)
+        .filter(|&(_, y)| y <= farthest)
+    {
+        extents[(y - nearest) as usize] = x;
+    }
+
+    (first_row..=last_row).filter_map(move |y| {
+        let extent = extents[(distance(y) - nearest) as usize] as f32;
+        let span = Span {
+            y: y as f32,
+            x_start: (center.x - extent + 1.0).max(min_x),
+            x_end: (center.x + extent).min(max_x),
+        };
+
+        (span.x_start < span.x_end).then_some(span)
+    })
+}
+
+/// Computes the points of the quarter of an ellipse centered on the origin that goes
+/// clockwise from `(0, radius_y)` to `(radius_x, 0)` using the midpoint ellipse algorithm.
+///
+/// The decision variable is scaled by 4 so it stays an integer. It grows with the fourth power
+/// of the radii, so it is kept in 128 bits.
+struct MidpointEllipse {
+    rx2: i128,
+    ry2: i128,
+    x: i128,
+    y: i128,
+    decision: i128,
+    /// Whether the points are still on the part where the slope is flatter than 45 degrees,
+    /// which steps along x instead of y.
+    flat: bool,
+}
+
+impl MidpointEllipse {
+    fn new(radius_x: i64, radius_y: i64) -> Self {
+        let (radius_x, radius_y) = (radius_x as i128, radius_y as i128);
+        let (rx2, ry2) = (radius_x * radius_x, radius_y * radius_y);
+
+        MidpointEllipse {
+            rx2,
+            ry2,
+            x: 0,
+            y: radius_y,
+            decision: 4 * ry2 - 4 * rx2 * radius_y + rx2,
+            flat: true,
+        }
+    }
+}
+
+impl Iterator for MidpointEllipse {
+    type Item = (i64, i64);
+
+    fn next(&mut self) -> Option<(i64, i64)> {
+        let MidpointEllipse {
+            rx2,
+            ry2,
+            x,
+            y,
+            decision,
+            flat,
+        } = self;
+
+        if *y < 0 {
+            return None;
+        }
+        let point = (*x as i64, *y as i64);
+
+        if *flat {
+            *x += 1;
+            if *decision < 0 {
+                *decision += 4 * (2 * *ry2 * *x + *ry2);
+            } else {
+                *y -= 1;
+                *decision += 4 * (2 * *ry2 * *x - 2 * *rx2 * *y + *ry2);
+            }
+
+            if 2 * *ry2 * *x >= 2 * *rx2 * *y {
+                *flat = false;
+                *decision =
+                    *ry2 * (2 * *x + 1).pow(2) + 4 * *rx2 * (*y - 1).pow(2) - 4 * *rx2 * *ry2;
+            }
+        } else {
+            *y -= 1;
+            if *decision > 0 {
+                *decision += 4 * (*rx2 - 2 * *rx2 * *y);
+            } else {
+                *x += 1;
+                *decision += 4 * (2 * *ry2 * *x - 2 * *rx2 * *y + *rx2);
+            }
+        }
+
+        Some(point)
+    }
+}
+
+/// Computes the fragments of a line one pixel wide going from `p1` to `p2`.
+///
+/// The line is clipped to `bounds`, so only its visible part is returned. Anti aliased lines
+/// also drop the pixels next to the line that fall outside of `bounds`.
+fn hairline(
+    p1: glm::Vec3,
+    p2: glm::Vec3,
+    bounds: &Rect,
+    anti_aliasing: bool,
+) -> Box<dyn Iterator<Item = Fragment>> {
+    let bounds = *bounds;
+
+    match clip_line(p1, p2, &bounds) {
+        None => Box::new(std::iter::empty()),
+        Some((p1, p2)) if anti_aliasing => {
+            Box::new(wu_line(p1, p2).filter(move |f| bounds.contains(&f.point)))
+        }
+        Some((p1, p2)) => Box::new(dda_line(p1, p2)),
+    }
+}
+
+/// Computes the points of the line going from `p1` to `p2` with a digital differential analyzer.
+///
+/// The line is sampled on every pixel center along its major axis between the pixels of both
+/// endpoints, so endpoints can lie anywhere inside a pixel and the amount of steps
+/// is known before starting.
+fn dda_line(p1: glm::Vec3, p2: glm::Vec3) -> impl Iterator<Item = Fragment> {
+    let delta = p2 - p1;
+    let steep = delta.y.abs() > delta.x.abs();
+    let (major_start, major_end, minor_start, minor_end) = if steep {
+        (p1.y, p2.y, p1.x, p2.x)
+    } else {
+        (p1.x, p2.x, p1.y, p2.y)
+    };
+    let slope = if major_end == major_start {
+        0.0
+    } else {
+        (minor_end - minor_start) / (major_end - major_start)
+    };
+    let minor_min = minor_start.min(minor_end);
+    let minor_max = minor_start.max(minor_end);
+
+    let first_pixel = major_start.round();
+    let steps = (major_end.round() - first_pixel).abs() as usize;
+    let direction = (major_end - major_start).signum();
+
+    (0..=steps).map(move |i| {
+        let major = first_pixel + direction * i as f32;
+        // The first and last pixel centers may be slightly past the endpoints.
+        let minor = (minor_start + (major - major_start) * slope)
+            .clamp(minor_min, minor_max)
+            .round();
+
+        if steep {
+            Fragment::solid(Vec3::new(minor, major, 0.0))
+        } else {
+            Fragment::solid(Vec3::new(major, minor, 0.0))
+        }
+    })
+}
+
+/// Computes the points of the line going from `p1` to `p2` using Xiaolin Wu's algorithm.
+///
+/// Every step along the major axis paints the two pixels straddling the line,
+/// each one covered according to how close its center is to the line.
+/// The steps go from `p1` to `p2`, so the fragments around each endpoint come out together.
+fn wu_line(p1: glm::Vec3, p2: glm::Vec3) -> impl Iterator<Item = Fragment> {
+    let steep = (p2.y - p1.y).abs() > (p2.x - p1.x).abs();
+
+    // Work on a line that goes left to right with a slope between -1 and 1.
+    let (mut x0, mut y0, mut x1, mut y1) = if steep {
+        (p1.y, p1.x, p2.y, p2.x)
+    } else {
+        (p1.x, p1.y, p2.x, p2.y)
+    };
+    let reversed = x0 > x1;
+    if reversed {
+        std::mem::swap(&mut x0, &mut x1);
+        std::mem::swap(&mut y0, &mut y1);
+    }
+
+    let dx = x1 - x0;
+    let gradient = if dx == 0.0 { 1.0 } else { (y1 - y0) / dx };
+
+    // The endpoints only cover the part of their pixel that the line goes through.
+    let first_x = x0.round();
+    let last_x = x1.round();
+    let first_gap = 1.0 - fractional_part(x0 + 0.5);
+    let last_gap = fractional_part(x1 + 0.5);
+    let columns = (last_x - first_x) as usize;
+
+    (0..=columns)
+        .flat_map(move |i| {
+            let column = if reversed { columns - i } else { i };
+            let x = first_x + column as f32;
+            let y = y0 + gradient * (x - x0);
+            let x_gap = match (column == 0, column == columns) {
+                (true, true) => first_gap + last_gap - 1.0,
+                (true, false) => first_gap,
+                (false, true) => last_gap,
+                (false, false) => 1.0,
+            };
+
+            [
+                (x, y.floor(), (1.0 - fractional_part(y)) * x_gap),
+                (x, y.floor() + 1.0, fractional_part(y) * x_gap),
+            ]
+        })
+        .filter(|(_, _, coverage)| *coverage > 0.0)
+        .map(move |(major, minor, coverage)| {
+            let point = if steep {
+                Vec3::new(minor, major, 0.0)
+            } else {
+                Vec3::new(major, minor, 0.0)
+            };
+            Fragment { point, coverage }
+        })
+}
+
+/// Computes the distance from `value` to the integer right below it.
+fn fractional_part(value: f32) -> f32 {
+    value - value.floor()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, f32::consts::PI};
+
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::shapes;
+
+    /// Creates a 5x5 framebuffer with a white diagonal line going from the top left corner.
+    fn diagonal_framebuffer() -> Framebuffer {
+        let mut framebuffer = Framebuffer::new(5, 5);
+        framebuffer.clear();
+        framebuffer
+            .line(Vec3::new(0.0, 0.0, 0.0), Vec3::new(4.0, 4.0, 0.0))
+            .paint()
+            .unwrap();
+
+        framebuffer
+    }
+
+    #[test]
+    fn test_flood_fill_four_connected_stops_at_diagonal() {
+        let mut framebuffer = diagonal_framebuffer();
+
+        framebuffer
+            .flood_fill(4, 0, 0xff0000, Connectivity::Four)
+            .unwrap();
+
+        assert_eq!(u32::from(framebuffer.get_color(3, 1).unwrap()), 0xff0000);
+        assert_eq!(u32::from(framebuffer.get_color(2, 2).unwrap()), 0xffffff);
+        assert_eq!(u32::from(framebuffer.get_color(0, 4).unwrap()), 0x000000);
+    }
+
+    #[test]
+    fn test_flood_fill_eight_connected_crosses_diagonal() {
+        let mut framebuffer = diagonal_framebuffer();
+
+        framebuffer
+            .flood_fill(4, 0, 0xff0000, Connectivity::Eight)
+            .unwrap();
+
+        assert_eq!(u32::from(framebuffer.get_color(0, 4).unwrap()), 0xff0000);
+        assert_eq!(u32::from(framebuffer.get_color(2, 2).unwrap()), 0xffffff);
+    }
+
+    #[test]
+    fn test_boundary_fill_with_same_color_as_region() {
+        let mut framebuffer = diagonal_framebuffer();
+        framebuffer.set_current_color(0x00ff00);
+        framebuffer.paint_point(Vec3::new(4.0, 0.0, 0.0)).unwrap();
+
+        framebuffer
+            .boundary_fill(3, 0, 0x00ff00, 0xffffff, Connectivity::Four)
+            .unwrap();
+
+        assert_eq!(u32::from(framebuffer.get_color(4, 3).unwrap()), 0x00ff00);
+        assert_eq!(u32::from(framebuffer.get_color(0, 4).unwrap()), 0x000000);
+    }
+
+    #[test]
+    fn test_flood_fill_large_region() {
+        let mut framebuffer = Framebuffer::new(800, 800);
+        framebuffer.clear();
+
+        framebuffer
+            .flood_fill(400, 400, 0x0000ff, Connectivity::Eight)
+            .unwrap();
+
+        assert_eq!(
+            u32::from(framebuffer.get_color(799, 799).unwrap()),
+            0x0000ff
+        );
+    }
+
+    #[test]
+    fn test_triangle_interpolates_vertex_colors() {
+        let mut framebuffer = Framebuffer::new(20, 20);
+        framebuffer.clear();
+
+        framebuffer
+            .triangle(
+                [
+                    Vec3::new(0.0, 0.0, 0.0),
+                    Vec3::new(18.0, 0.0, 0.0),
+                    Vec3::new(0.0, 18.0, 0.0),
+                ],
+                [0xff0000.into(), 0x00ff00.into(), 0x0000ff.into()],
+            )
+            .unwrap();
+
+        let is_close = |x, y, expected: (u8, u8, u8)| {
+            let Color { r, g, b, .. } = framebuffer.get_color(x, y).unwrap();
+            r.abs_diff(expected.0) <= 1
+                && g.abs_diff(expected.1) <= 1
+                && b.abs_diff(expected.2) <= 1
+        };
+
+        assert_eq!(u32::from(framebuffer.get_color(0, 0).unwrap()), 0xff0000);
+        assert!(is_close(9, 0, (127, 127, 0)));
+        assert!(is_close(0, 9, (127, 0, 127)));
+        assert!(is_close(6, 6, (85, 85, 85)));
+
+        // The vertices on the right and bottom edges are left for the neighbouring triangles.
+        assert_eq!(u32::from(framebuffer.get_color(18, 0).unwrap()), 0x000000);
+        assert_eq!(u32::from(framebuffer.get_color(0, 18).unwrap()), 0x000000);
+        assert_eq!(u32::from(framebuffer.get_color(10, 10).unwrap()), 0x000000);
+    }
+
+    #[test]
+    fn test_long_dashed_lines_only_dash_their_visible_part() {
+        let mut framebuffer = Framebuffer::new(100, 100);
+        framebuffer.set_line_dash(vec![6.0, 4.0]);
+        let (start, end) = (Vec3::new(-1.0e8, 50.0, 0.0), Vec3::new(1.0e8, 50.0, 0.0));
+
+        for width in [1.0, 5.0] {
+            framebuffer.clear();
+            framebuffer.set_line_width(width);
+            framebuffer.line(start, end).paint().unwrap();
+
+            let painted = (0..100)
+                .filter(|&x| u32::from(framebuffer.get_color(x, 50).unwrap()) == 0xffffff)
+                .count();
+            assert!((50..=70).contains(&painted), "{painted}");
+        }
+    }
+
+    #[test]
+    fn test_anti_aliased_lines_touching_the_edges() {
+        let mut framebuffer = Framebuffer::new(100, 100);
+        framebuffer.clear();
+        framebuffer.set_anti_aliasing(true);
+
+        let lines = [
+            ((0.4, 0.0), (50.0, 30.0)),
+            ((99.0, 20.6), (40.0, 60.0)),
+            ((20.0, 99.0), (70.0, 99.0)),
+            ((0.0, 80.0), (30.3, 99.0)),
+            ((30.0, 10.0), (99.0, 0.3)),
+        ];
+        for ((x1, y1), (x2, y2)) in lines {
+            let (p1, p2) = (Vec3::new(x1, y1, 0.0), Vec3::new(x2, y2, 0.0));
+            assert!(framebuffer.line(p1, p2).paint().is_ok());
+            assert!(framebuffer.line(p2, p1).paint().is_ok());
+        }
+        assert_eq!(u32::from(framebuffer.get_color(45, 99).unwrap()), 0xffffff);
+    }
+
+    #[test]
+    fn test_get_color_rejects_pixels_past_the_edges() {
+        let mut framebuffer = Framebuffer::new(100, 100);
+        framebuffer.clear();
+
+        assert!(framebuffer.get_color(99, 99).is_ok());
+        assert!(matches!(
+            framebuffer.get_color(100, 99),
+            Err(GetColorErrors::XTooLarge)
+        ));
+        assert!(matches!(
+            framebuffer.get_color(99, 100),
+            Err(GetColorErrors::YTooLarge)
+        ));
+    }
+
+    #[test]
+    fn test_single_points_outside_are_clipped() {
+        let mut framebuffer = Framebuffer::new(10, 10);
+        framebuffer.clear();
+
+        assert!(framebuffer
+            .polygon(vec![Vec3::new(-3.0, 5.0, 0.0)])
+            .paint()
+            .is_ok());
+        assert!(framebuffer
+            .polyline(vec![Vec3::new(5.0, 12.0, 0.0)])
+            .paint()
+            .is_ok());
+        assert_eq!(
+            framebuffer.polygon(vec![Vec3::new(3.0, 5.0, 0.0)]).count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_single_color_triangle_is_exact() {
+        let mut framebuffer = Framebuffer::new(40, 40);
+        framebuffer.set_background_color(0x0000ff);
+        framebuffer.clear();
+
+        framebuffer
+            .triangle(
+                [
+                    Vec3::new(1.0, 2.0, 0.0),
+                    Vec3::new(37.0, 5.0, 0.0),
+                    Vec3::new(11.0, 38.0, 0.0),
+                ],
+                [Color::white(); 3],
+            )
+            .unwrap();
+
+        let painted: Vec<u32> = framebuffer
+            .buffer
+            .iter()
+            .copied()
+            .filter(|&pixel| pixel != Color::from(0x0000ff).to_argb())
+            .collect();
+        assert!(painted.len() > 400);
+        assert!(painted
+            .iter()
+            .all(|&pixel| pixel == Color::white().to_argb()));
+    }
+
+    #[test]
+    fn test_adjacent_triangles_tile_without_gaps_or_overlap() {
+        let a = Vec3::new(0.0, 0.0, 0.0);
+        let b = Vec3::new(8.0, 0.0, 0.0);
+        let c = Vec3::new(8.0, 8.0, 0.0);
+        let d = Vec3::new(0.0, 8.0, 0.0);
+        let colors = [Color::white(); 3];
+
+        let mut first = Framebuffer::new(10, 10);
+        first.clear();
+        first.triangle([a, b, c], colors).unwrap();
+
+        let mut second = Framebuffer::new(10, 10);
+        second.clear();
+        second.triangle([a, d, c], colors).unwrap();
+
+        for y in 0..10 {
+            for x in 0..10 {
+                let in_first = u32::from(first.get_color(x, y).unwrap()) != 0;
+                let in_second = u32::from(second.get_color(x, y).unwrap()) != 0;
+
+                assert!(!(in_first && in_second), "pixel ({x}, {y}) painted twice");
+                assert_eq!(in_first || in_second, x < 8 && y < 8, "pixel ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn test_polygon_partly_off_screen_is_clipped() {
+        let mut framebuffer = Framebuffer::new(10, 10);
+        framebuffer.clear();
+
+        framebuffer
+            .paint_filled_polygon(
+                vec![
+                    Vec3::new(-5.0, -5.0, 0.0),
+                    Vec3::new(15.0, -5.0, 0.0),
+                    Vec3::new(15.0, 5.0, 0.0),
+                    Vec3::new(-5.0, 5.0, 0.0),
+                ],
+                0xff0000,
+                0xffffff,
+            )
+            .unwrap();
+
+        assert_eq!(u32::from(framebuffer.get_color(0, 0).unwrap()), 0xff0000);
+        assert_eq!(u32::from(framebuffer.get_color(9, 4).unwrap()), 0xff0000);
+        assert_eq!(u32::from(framebuffer.get_color(3, 5).unwrap()), 0xffffff);
+        assert_eq!(u32::from(framebuffer.get_color(3, 6).unwrap()), 0x000000);
+    }
+
+    #[test]
+    fn test_anti_aliased_line_coverage() {
+        let mut framebuffer = Framebuffer::new(10, 10);
+        framebuffer.set_anti_aliasing(true);
+
+        let horizontal = framebuffer
+            .line(Vec3::new(1.0, 2.0, 0.0), Vec3::new(5.0, 2.0, 0.0))
+            .collect::<Vec<Fragment>>();
+        let coverages: Vec<f32> = horizontal.iter().map(|f| f.coverage).collect();
+        assert_eq!(coverages, vec![0.5, 1.0, 1.0, 1.0, 0.5]);
+        assert!(horizontal.iter().all(|f| f.point.y == 2.0));
+
+        // Each column is split between the two pixels straddling the line.
+        let diagonal = framebuffer
+            .line(Vec3::new(0.0, 0.0, 0.0), Vec3::new(8.0, 4.0, 0.0))
+            .collect::<Vec<Fragment>>();
+        for x in 1..8 {
+            let column: f32 = diagonal
+                .iter()
+                .filter(|f| f.point.x == x as f32)
+                .map(|f| f.coverage)
+                .sum();
+            assert_eq!(column, 1.0);
+        }
+        let middle = diagonal
+            .iter()
+            .find(|f| f.point == Vec3::new(3.0, 1.0, 0.0))
+            .unwrap();
+        assert_eq!(middle.coverage, 0.5);
+    }
+
+    #[test]
+    fn test_wide_line_keeps_its_width_at_any_angle() {
+        let mut framebuffer = Framebuffer::new(40, 40);
+        framebuffer.set_line_width(5.0);
+
+        let horizontal = framebuffer
+            .line(Vec3::new(5.0, 20.0, 0.0), Vec3::new(35.0, 20.0, 0.0))
+            .collect::<Vec<Fragment>>();
+        let column: Vec<f32> = horizontal
+            .iter()
+            .filter(|f| f.point.x == 20.0)
+            .map(|f| f.point.y)
+            .collect();
+        assert_eq!(column, vec![18.0, 19.0, 20.0, 21.0, 22.0]);
+
+        let diagonal = framebuffer
+            .line(Vec3::new(5.0, 5.0, 0.0), Vec3::new(35.0, 35.0, 0.0))
+            .collect::<Vec<Fragment>>();
+        let area = 5.0 * (30.0f32 * 2.0f32.sqrt());
+        assert!((diagonal.len() as f32 - area).abs() < area * 0.05);
+    }
+
+    #[test]
+    fn test_line_crossing_the_edges_paints_its_visible_part() {
+        let mut framebuffer = Framebuffer::new(10, 10);
+        framebuffer.clear();
+
+        framebuffer
+            .line(Vec3::new(-10.0, -10.0, 0.0), Vec3::new(20.0, 20.0, 0.0))
+            .paint()
+            .unwrap();
+
+        for i in 0..10 {
+            assert_eq!(u32::from(framebuffer.get_color(i, i).unwrap()), 0xffffff);
+        }
+
+        assert!(framebuffer
+            .line(Vec3::new(-10.0, 5.0, 0.0), Vec3::new(-1.0, 5.0, 0.0))
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn test_line_with_fractional_endpoints() {
+        let points: Vec<(f32, f32)> = dda_line(Vec3::new(0.5, 0.0, 0.0), Vec3::new(10.3, 4.0, 0.0))
+            .map(|f| (f.point.x, f.point.y))
+            .collect();
+
+        assert_eq!(points.len(), 10);
+        assert_eq!(points.first(), Some(&(1.0, 0.0)));
+        assert_eq!(points.last(), Some(&(10.0, 4.0)));
+    }
+
+    proptest! {
+        #[test]
+        fn prop_line_is_connected_and_reaches_both_ends(
+            x0 in -1000.0f32..1000.0,
+            y0 in -1000.0f32..1000.0,
+            x1 in -1000.0f32..1000.0,
+            y1 in -1000.0f32..1000.0,
+        ) {
+            let p1 = Vec3::new(x0, y0, 0.0);
+            let p2 = Vec3::new(x1, y1, 0.0);
+
+            let points: Vec<Vec3> = dda_line(p1, p2).map(|f| f.point).collect();
+            let major_length = (x1.round() - x0.round()).abs().max((y1.round() - y0.round()).abs());
+
+            prop_assert!(points.len() as f32 <= major_length + 2.0);
+            prop_assert!((points[0] - p1).abs().max() <= 1.0);
+            prop_assert!((points[points.len() - 1] - p2).abs().max() <= 1.0);
+            for pair in points.windows(2) {
+                let step = (pair[1] - pair[0]).abs();
+                prop_assert!(step.x <= 1.0 && step.y <= 1.0 && step.x + step.y >= 1.0);
+            }
+        }
+
+        #[test]
+        fn prop_line_points_are_whole_pixels(
+            x0 in -1000.0f32..1000.0,
+            y0 in -1000.0f32..1000.0,
+            x1 in -1000.0f32..1000.0,
+            y1 in -1000.0f32..1000.0,
+        ) {
+            let mut points = dda_line(Vec3::new(x0, y0, 0.0), Vec3::new(x1, y1, 0.0));
+
+            prop_assert!(points
+                .all(|f| f.point.x.fract() == 0.0 && f.point.y.fract() == 0.0));
+        }
+    }
+
+    #[test]
+    fn test_polyline_is_open_and_paints_vertices_once() {
+        let mut framebuffer = Framebuffer::new(10, 10);
+        let points = vec![
+            Vec3::new(1.0, 1.0, 0.0),
+            Vec3::new(8.0, 1.0, 0.0),
+            Vec3::new(8.0, 8.0, 0.0),
+        ];
+
+        let polyline = framebuffer.polyline(points.clone()).collect::<Vec<_>>();
+        assert_eq!(polyline.len(), 15);
+        assert!(!polyline.iter().any(|f| f.point == Vec3::new(4.0, 4.0, 0.0)));
+
+        let polygon = framebuffer.polygon(points).collect::<Vec<_>>();
+        assert!(polygon.iter().any(|f| f.point == Vec3::new(4.0, 4.0, 0.0)));
+
+        framebuffer.set_anti_aliasing(true);
+        let corner = framebuffer
+            .polyline(vec![
+                Vec3::new(1.0, 1.0, 0.0),
+                Vec3::new(8.0, 1.0, 0.0),
+                Vec3::new(8.0, 8.0, 0.0),
+            ])
+            .filter(|f| f.point == Vec3::new(8.0, 1.0, 0.0))
+            .collect::<Vec<Fragment>>();
+        assert_eq!(corner.len(), 1);
+        assert_eq!(corner[0].coverage, 1.0);
+    }
+
+    #[test]
+    fn test_canvas_rasterizes_lazily() {
+        let mut framebuffer = Framebuffer::new(10, 10);
+        framebuffer.clear();
+        framebuffer.set_anti_aliasing(true);
+
+        // The first vertex of a closed outline is only complete once the last edge comes back to it.
+        let square = framebuffer
+            .polygon(vec![
+                Vec3::new(2.0, 2.0, 0.0),
+                Vec3::new(7.0, 2.0, 0.0),
+                Vec3::new(7.0, 7.0, 0.0),
+                Vec3::new(2.0, 7.0, 0.0),
+            ])
+            .filter(|f| f.point == Vec3::new(2.0, 2.0, 0.0))
+            .collect::<Vec<Fragment>>();
+        assert_eq!(square.len(), 1);
+        assert_eq!(square[0].coverage, 1.0);
+
+        // Fragments taken out of the canvas are not painted.
+        framebuffer.set_anti_aliasing(false);
+        let mut canvas = framebuffer.line(Vec3::new(0.0, 5.0, 0.0), Vec3::new(9.0, 5.0, 0.0));
+        assert_eq!(
+            canvas.next().map(|f| f.point),
+            Some(Vec3::new(0.0, 5.0, 0.0))
+        );
+        canvas.paint().unwrap();
+        assert_eq!(u32::from(framebuffer.get_color(0, 5).unwrap()), 0x000000);
+        assert_eq!(u32::from(framebuffer.get_color(9, 5).unwrap()), 0xffffff);
+    }
+
+    #[test]
+    fn test_filled_path_follows_its_curves() {
+        let mut framebuffer = Framebuffer::new(20, 20);
+        framebuffer.clear();
+
+        // A half disc of radius 8 centered on (10, 10), made with a quadratic curve
+        // which bulges half as much as its control point.
+        let mut path = Path::new(Vec3::new(2.0, 10.0, 0.0));
+        path.quadratic_to(Vec3::new(10.0, -6.0, 0.0), Vec3::new(18.0, 10.0, 0.0));
+        path.close();
+        framebuffer.fill_path(&path).unwrap();
+
+        assert_eq!(u32::from(framebuffer.get_color(10, 3).unwrap()), 0xffffff);
+        assert_eq!(u32::from(framebuffer.get_color(10, 1).unwrap()), 0x000000);
+        assert_eq!(u32::from(framebuffer.get_color(3, 3).unwrap()), 0x000000);
+        assert_eq!(u32::from(framebuffer.get_color(10, 11).unwrap()), 0x000000);
+
+        let outline: Vec<Fragment> = framebuffer.path(&path).collect();
+        assert!(outline.iter().any(|f| f.point == Vec3::new(10.0, 2.0, 0.0)));
+        assert!(outline
+            .iter()
+            .any(|f| f.point == Vec3::new(10.0, 10.0, 0.0)));
+    }
+
+    #[test]
+    fn test_midpoint_circle_is_symmetric_and_close_to_its_radius() {
+        let mut framebuffer = Framebuffer::new(40, 40);
+        let center = Vec3::new(20.0, 20.0, 0.0);
+
+        let points: Vec<Vec3> = framebuffer.circle(center, 10.0).map(|f| f.point).collect();
+
+        let unique: HashSet<(i64, i64)> = points.iter().map(|p| (p.x as i64, p.y as i64)).collect();
+        assert_eq!(unique.len(), points.len());
+        for p in &points {
+            let offset = p - center;
+            assert!((glm::length(&offset) - 10.0).abs() < 0.5);
+            assert!(unique.contains(&((20.0 - offset.x) as i64, p.y as i64)));
+            assert!(unique.contains(&((20.0 + offset.y) as i64, (20.0 + offset.x) as i64)));
+        }
+        assert!(unique.contains(&(20, 10)) && unique.contains(&(30, 20)));
+    }
+
+    #[test]
+    fn test_filled_ellipses() {
+        let mut framebuffer = Framebuffer::new(40, 40);
+        framebuffer.clear();
+
+        framebuffer
+            .fill_circle(Vec3::new(20.0, 20.0, 0.0), 10.0)
+            .unwrap();
+        let painted = (0..40)
+            .flat_map(|y| (0..40).map(move |x| (x, y)))
+            .filter(|&(x, y)| u32::from(framebuffer.get_color(x, y).unwrap()) == 0xffffff)
+            .count() as f32;
+        let area = PI * 100.0;
+        assert!((painted - area).abs() < area * 0.03);
+
+        // Axis aligned fills reach the midpoint outline on every row without going past it.
+        framebuffer.clear();
+        let center = Vec3::new(20.0, 20.0, 0.0);
+        framebuffer.set_current_color(0xff0000);
+        framebuffer.fill_ellipse(center, 15.0, 7.0, 0.0).unwrap();
+        let outline: Vec<Vec3> = framebuffer
+            .ellipse(center, 15.0, 7.0, 0.0)
+            .map(|f| f.point)
+            .collect();
+        for y in 13..=27 {
+            let row = outline.iter().filter(|p| p.y == y as f32);
+            let right = row.map(|p| p.x as i64).max().unwrap();
+            for x in 0..40 {
+                let filled = u32::from(framebuffer.get_color(x, y).unwrap()) == 0xff0000;
+                assert_eq!(filled, (x as i64 - 20).abs() < right - 20, "({x}, {y})");
+            }
+        }
+
+        // A long ellipse going down and to the right, clipped by the framebuffer.
+        framebuffer.clear();
+        framebuffer.set_current_color(0xff0000);
+        framebuffer
+            .fill_ellipse(Vec3::new(30.0, 30.0, 0.0), 20.0, 3.0, PI / 4.0)
+            .unwrap();
+        assert_eq!(u32::from(framebuffer.get_color(39, 39).unwrap()), 0xff0000);
+        assert_eq!(u32::from(framebuffer.get_color(20, 20).unwrap()), 0xff0000);
+        assert_eq!(u32::from(framebuffer.get_color(36, 24).unwrap()), 0x000000);
+        assert_eq!(u32::from(framebuffer.get_color(24, 36).unwrap()), 0x000000);
+    }
+
+    #[test]
+    fn test_polygonal_ellipse_outlines_keep_their_radii() {
+        let mut framebuffer = Framebuffer::new(40, 40);
+        let center = Vec3::new(20.0, 20.0, 0.0);
+
+        // The border of an anti aliased circle reaches as far as its fill.
+        framebuffer.set_anti_aliasing(true);
+        let outline: Vec<Fragment> = framebuffer.circle(center, 10.4).collect();
+        assert!(outline
+            .iter()
+            .any(|f| f.point == Vec3::new(31.0, 20.0, 0.0) && f.coverage > 0.3));
+
+        // So does the border of a rotated one.
+        let outline: Vec<Fragment> = framebuffer.ellipse(center, 10.4, 6.0, PI / 2.0).collect();
+        assert!(outline
+            .iter()
+            .any(|f| f.point == Vec3::new(20.0, 31.0, 0.0) && f.coverage > 0.3));
+    }
+
+    #[test]
+    fn test_huge_and_partly_visible_ellipses() {
+        let mut framebuffer = Framebuffer::new(100, 100);
+        framebuffer.clear();
+
+        // Only the top of these circles is visible, near the middle of the framebuffer.
+        let center = Vec3::new(50.0, 100_050.0, 0.0);
+        let outline: Vec<Fragment> = framebuffer.circle(center, 100_000.0).collect();
+        assert!(outline
+            .iter()
+            .any(|f| f.point == Vec3::new(50.0, 50.0, 0.0)));
+        framebuffer.fill_circle(center, 100_000.0).unwrap();
+        assert_eq!(u32::from(framebuffer.get_color(50, 60).unwrap()), 0xffffff);
+        assert_eq!(u32::from(framebuffer.get_color(50, 40).unwrap()), 0x000000);
+
+        framebuffer.clear();
+        framebuffer
+            .fill_circle(Vec3::new(50.0, 150.0, 0.0), 90.0)
+            .unwrap();
+        assert_eq!(u32::from(framebuffer.get_color(50, 65).unwrap()), 0xffffff);
+        assert_eq!(u32::from(framebuffer.get_color(50, 55).unwrap()), 0x000000);
+        assert_eq!(u32::from(framebuffer.get_color(0, 99).unwrap()), 0xffffff);
+    }
+
+    #[test]
+    fn test_pie_slices_and_ring_segments() {
+        let mut framebuffer = Framebuffer::new(40, 40);
+        framebuffer.clear();
+        let center = Vec3::new(20.0, 20.0, 0.0);
+
+        // The quarter going clockwise from the right is the bottom right one on the screen.
+        framebuffer
+            .fill_pie(center, 15.0, 15.0, 0.0, PI / 2.0)
+            .unwrap();
+        assert_eq!(u32::from(framebuffer.get_color(25, 25).unwrap()), 0xffffff);
+        assert_eq!(u32::from(framebuffer.get_color(25, 15).unwrap()), 0x000000);
+        assert_eq!(u32::from(framebuffer.get_color(15, 25).unwrap()), 0x000000);
+
+        // A full ring leaves its middle empty and has no visible seam.
+        framebuffer.clear();
+        framebuffer
+            .fill_ring_segment(center, 5.0, 15.0, 0.0, 2.0 * PI)
+            .unwrap();
+        assert_eq!(u32::from(framebuffer.get_color(20, 20).unwrap()), 0x000000);
+        for (x, y) in [(30, 20), (10, 20), (20, 30), (20, 10)] {
+            assert_eq!(u32::from(framebuffer.get_color(x, y).unwrap()), 0xffffff);
+        }
+
+        // Its outline is two circles, with no spoke joining them along the seam.
+        let outline: Vec<Vec3> = framebuffer
+            .ring_segment(center, 5.0, 15.0, 0.0, 2.0 * PI)
+            .map(|f| f.point)
+            .collect();
+        let unique: HashSet<(i64, i64)> =
+            outline.iter().map(|p| (p.x as i64, p.y as i64)).collect();
+        assert_eq!(unique.len(), outline.len());
+        assert!((27..=32).all(|x| !unique.contains(&(x, 20))));
+        assert!(unique.contains(&(25, 20)) && unique.contains(&(35, 20)));
+
+        let arc: Vec<Fragment> = framebuffer.arc(center, 10.0, 10.0, PI, PI / 2.0).collect();
+        assert!(arc.iter().any(|f| f.point == Vec3::new(10.0, 20.0, 0.0)));
+        assert!(arc.iter().any(|f| f.point == Vec3::new(20.0, 10.0, 0.0)));
+        assert!(arc.iter().all(|f| f.point.x <= 20.0 && f.point.y <= 20.0));
+
+        // Without any tolerance arcs are still split into a bounded amount of lines.
+        framebuffer.set_curve_tolerance(0.0);
+        assert!(framebuffer
+            .arc(center, 100.0, 100.0, 0.0, 1.0)
+            .paint()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_translucent_colors_blend_with_the_buffer() {
+        let mut framebuffer = Framebuffer::new(10, 10);
+        framebuffer.clear();
+        framebuffer.set_current_color(0xff0000);
+        framebuffer
+            .fill_polygon(&shapes::rectangle(Vec3::new(3.0, 5.0, 0.0), 6.0, 10.0, 0.0))
+            .unwrap();
+
+        // Half transparent blue over red on the left and over black on the right.
+        framebuffer.set_current_color(0x800000ff);
+        framebuffer
+            .fill_polygon(&shapes::rectangle(Vec3::new(5.0, 5.0, 0.0), 10.0, 4.0, 0.0))
+            .unwrap();
+        framebuffer.paint_point(Vec3::new(8.0, 8.0, 0.0)).unwrap();
+
+        assert_eq!(u32::from(framebuffer.get_color(1, 5).unwrap()), 0x7f0080);
+        assert_eq!(u32::from(framebuffer.get_color(8, 5).unwrap()), 0x000080);
+        assert_eq!(u32::from(framebuffer.get_color(8, 8).unwrap()), 0x000080);
+        assert_eq!(u32::from(framebuffer.get_color(1, 1).unwrap()), 0xff0000);
+    }
+
+    #[test]
+    fn test_composite_operation_applies_to_every_painting_path() {
+        let mut framebuffer = Framebuffer::new(10, 10);
+        framebuffer.set_background_color(Color::new_rgba(0, 0, 0, 0));
+        framebuffer.clear();
+        framebuffer.set_current_color(0xff0000);
+        framebuffer
+            .fill_polygon(&shapes::rectangle(Vec3::new(2.5, 4.5, 0.0), 5.0, 9.0, 0.0))
+            .unwrap();
+
+        // Only the part over the red rectangle is painted.
+        framebuffer.set_composite_operation(CompositeOperation::SourceAtop);
+        framebuffer.set_current_color(0x0000ff);
+        framebuffer
+            .line(Vec3::new(0.0, 2.0, 0.0), Vec3::new(9.0, 2.0, 0.0))
+            .paint()
+            .unwrap();
+        assert_eq!(u32::from(framebuffer.get_color(4, 2).unwrap()), 0x0000ff);
+        assert_eq!(framebuffer.get_color(5, 2).unwrap().a, 0);
+
+        // Punches a transparent hole, which a later fill behind it shows through.
+        framebuffer.set_composite_operation(CompositeOperation::Clear);
+        framebuffer
+            .fill_polygon(&shapes::rectangle(Vec3::new(2.0, 7.0, 0.0), 2.0, 2.0, 0.0))
+            .unwrap();
+        assert_eq!(framebuffer.get_color(1, 6).unwrap().a, 0);
+
+        framebuffer.set_composite_operation(CompositeOperation::DestinationOver);
+        framebuffer.set_current_color(0x00ff00);
+        framebuffer
+            .flood_fill(1, 6, 0x00ff00, Connectivity::Four)
+            .unwrap();
+        assert_eq!(u32::from(framebuffer.get_color(1, 6).unwrap()), 0x00ff00);
+        assert_eq!(u32::from(framebuffer.get_color(1, 1).unwrap()), 0xff0000);
+        assert_eq!(framebuffer.get_color(7, 7).unwrap().a, 0);
+    }
+
+    #[test]
+    fn test_blend_mode_mixes_with_the_buffer() {
+        let mut framebuffer = Framebuffer::new(10, 10);
+        framebuffer.set_background_color(0x808080);
+        framebuffer.clear();
+
+        framebuffer.set_blend_mode(BlendMode::Multiply);
+        framebuffer.set_current_color(0xff8000);
+        framebuffer
+            .fill_polygon(&shapes::rectangle(Vec3::new(5.0, 5.0, 0.0), 10.0, 4.0, 0.0))
+            .unwrap();
+        assert_eq!(u32::from(framebuffer.get_color(5, 5).unwrap()), 0x804000);
+        assert_eq!(u32::from(framebuffer.get_color(5, 1).unwrap()), 0x808080);
+
+        // Painting black with difference leaves the buffer as it was.
+        framebuffer.set_blend_mode(BlendMode::Difference);
+        framebuffer.set_current_color(0x000000);
+        framebuffer
+            .line(Vec3::new(0.0, 5.0, 0.0), Vec3::new(9.0, 5.0, 0.0))
+            .paint()
+            .unwrap();
+        assert_eq!(u32::from(framebuffer.get_color(5, 5).unwrap()), 0x804000);
     }
 }
@@ -1,8 +1,34 @@
 pub mod bmp;
+pub mod clipping;
 pub mod color;
+pub mod compositing;
+pub mod curve;
 pub mod framebuffer;
+pub mod scanline;
+pub mod shapes;
+pub mod stroke;
+pub mod triangulation;
 extern crate nalgebra_glm as glm;
 
 pub fn are_equal(first: f32, second: f32, eps: f32) -> bool {
     (first - second).abs() <= eps
 }
+
+/// Computes twice the signed area of the triangle `a`, `b`, `p`.
+///
+/// The sign tells on which side of the line going from `a` to `b` the point `p` is.
+pub fn edge_function(a: &glm::Vec3, b: &glm::Vec3, p: &glm::Vec3) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Computes the signed area of the closed ring formed by `points` using the shoelace formula.
+///
+/// Since y grows downwards, the area is positive when the ring is clockwise on the screen.
+pub fn signed_area(points: &[glm::Vec3]) -> f32 {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.x * b.y - b.x * a.y)
+        .sum::<f32>()
+        / 2.0
+}
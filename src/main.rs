@@ -1,9 +1,10 @@
-use std::error::Error;
+use std::{error::Error, f32::consts::PI};
 extern crate nalgebra_glm as glm;
 
 use filling_polygon::{
     color::Color,
-    framebuffer::{self, Framebuffer},
+    framebuffer::Framebuffer,
+    shapes::{rectangle, star},
 };
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -14,8 +15,9 @@ fn main() -> Result<(), Box<dyn Error>> {
     draw_star(&mut framebuffer)?;
     draw_square(&mut framebuffer)?;
     draw_triangle(&mut framebuffer)?;
-    draw_teapot(&mut framebuffer)?;
+    // The teapot with a hole goes first, otherwise the hole would show the solid teapot.
     draw_teapothole(&mut framebuffer)?;
+    draw_teapot(&mut framebuffer)?;
 
     Ok(())
 }
@@ -42,15 +44,13 @@ fn draw_teapothole(framebuffer: &mut Framebuffer) -> Result<(), Box<dyn Error>>
         glm::Vec3::new(466.0, 180.0, 0.0),
     ];
 
-    framebuffer.paint_filled_polygon(points, 0x0a5c36, 0xffffff)?;
-
     let hole_points = vec![
         glm::Vec3::new(682.0, 175.0, 0.0),
         glm::Vec3::new(708.0, 120.0, 0.0),
         glm::Vec3::new(735.0, 148.0, 0.0),
         glm::Vec3::new(739.0, 170.0, 0.0),
     ];
-    framebuffer.paint_filled_polygon(hole_points, 0x000000, 0xffffff)?;
+    framebuffer.paint_filled_polygon_with_holes(points, vec![hole_points], 0x0a5c36, 0xffffff)?;
 
     framebuffer.save("poligon5.bmp")?;
 
@@ -98,12 +98,7 @@ fn draw_triangle(framebuffer: &mut Framebuffer) -> Result<(), Box<dyn Error>> {
 }
 
 fn draw_square(framebuffer: &mut Framebuffer) -> Result<(), Box<dyn Error>> {
-    let points = vec![
-        glm::Vec3::new(321.0, 335.0, 0.0),
-        glm::Vec3::new(288.0, 286.0, 0.0),
-        glm::Vec3::new(339.0, 251.0, 0.0),
-        glm::Vec3::new(374.0, 302.0, 0.0),
-    ];
+    let points = rectangle(glm::Vec3::new(330.5, 293.5, 0.0), 60.0, 60.0, -0.6);
     framebuffer.paint_filled_polygon(points, 0x0000ff, 0xffffff)?;
     framebuffer.save("poligon2.bmp")?;
 
@@ -111,19 +106,8 @@ fn draw_square(framebuffer: &mut Framebuffer) -> Result<(), Box<dyn Error>> {
 }
 
 fn draw_star(framebuffer: &mut Framebuffer) -> Result<(), Box<dyn Error>> {
-    let points = vec![
-        glm::Vec3::new(165.0, 380.0, 0.0),
-        glm::Vec3::new(185.0, 360.0, 0.0),
-        glm::Vec3::new(180.0, 330.0, 0.0),
-        glm::Vec3::new(207.0, 345.0, 0.0),
-        glm::Vec3::new(233.0, 330.0, 0.0),
-        glm::Vec3::new(230.0, 360.0, 0.0),
-        glm::Vec3::new(250.0, 380.0, 0.0),
-        glm::Vec3::new(220.0, 385.0, 0.0),
-        glm::Vec3::new(205.0, 410.0, 0.0),
-        glm::Vec3::new(193.0, 383.0, 0.0),
-    ];
-    // framebuffer.polygon(points, 0xe5de00).paint()?;
+    // Upside down, with a tip pointing to the bottom.
+    let points = star(glm::Vec3::new(207.0, 366.0, 0.0), 44.0, 22.0, 5, PI);
     framebuffer.paint_filled_polygon(points, 0xe5de00, 0xffffff)?;
     framebuffer.save("poligon1.bmp")?;
 
@@ -0,0 +1,455 @@
+use std::ops::Range;
+
+use crate::signed_area;
+
+/// Rule used to decide which regions of a polygon are inside of it.
+///
+/// Both rules give the same result for simple polygons,
+/// they only differ on polygons whose edges intersect each other.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FillRule {
+    /// A point is inside if a ray from it crosses the outline an odd number of times.
+    EvenOdd,
+    /// A point is inside if the outline winds around it a non zero number of times.
+    #[default]
+    NonZero,
+}
+
+/// A non horizontal edge of a polygon, stored from top to bottom.
+#[derive(Debug, Clone, Copy)]
+struct Edge {
+    y_top: f32,
+    y_bottom: f32,
+    x_top: f32,
+    inverse_slope: f32,
+    /// 1 if the edge goes downwards in the original polygon, -1 otherwise.
+    winding: i32,
+}
+
+impl Edge {
+    /// Creates the edge that joins `a` and `b`.
+    ///
+    /// Returns `None` for horizontal edges since they never cross a scanline.
+    fn new(a: &glm::Vec3, b: &glm::Vec3) -> Option<Self> {
+        if a.y == b.y {
+            return None;
+        }
+
+        let (top, bottom, winding) = if a.y < b.y { (a, b, 1) } else { (b, a, -1) };
+
+        Some(Edge {
+            y_top: top.y,
+            y_bottom: bottom.y,
+            x_top: top.x,
+            inverse_slope: (bottom.x - top.x) / (bottom.y - top.y),
+            winding,
+        })
+    }
+
+    /// Computes the x coordinate where the edge crosses the scanline `y`.
+    fn x_at(&self, y: f32) -> f32 {
+        self.x_top + (y - self.y_top) * self.inverse_slope
+    }
+}
+
+/// A horizontal run of the polygon interior on the scanline `y`.
+///
+/// The run covers every x between `x_start` and `x_end`, both inclusive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Span {
+    pub y: f32,
+    pub x_start: f32,
+    pub x_end: f32,
+}
+
+impl Span {
+    /// Computes the x coordinates of the pixels whose centers are inside the span.
+    ///
+    /// Follows the top-left rule: a center that lies exactly on `x_start` is inside the span
+    /// but one that lies exactly on `x_end` isn't. That way two polygons sharing an edge
+    /// never paint the same pixel and never leave a gap between them.
+    pub fn pixels(&self) -> Range<i64> {
+        self.x_start.ceil() as i64..self.x_end.ceil() as i64
+    }
+}
+
+/// Sorted table of the edges of a polygon used to compute its interior one scanline at a time.
+///
+/// Scanlines are sampled on integer y coordinates, which are the pixel centers according to
+/// the rounding done by `Framebuffer::paint_point`. An edge is considered to cross a scanline `y`
+/// when `y_top <= y < y_bottom`, so vertices shared by two edges are only counted once
+/// and a scanline lying exactly on a horizontal edge only belongs to the polygon below it.
+#[derive(Debug)]
+pub struct EdgeTable {
+    edges: Vec<Edge>,
+    y_max: f32,
+}
+
+impl EdgeTable {
+    /// Builds the edge table of the closed polygon formed by `points`.
+    pub fn new(points: &[glm::Vec3]) -> Self {
+        EdgeTable::with_holes(points, &[])
+    }
+
+    /// Builds the edge table of the closed polygon formed by `outer`
+    /// with every ring inside `holes` cut out of it.
+    ///
+    /// The holes are wound in the opposite direction of the outer ring,
+    /// so they are left empty by both `FillRule::EvenOdd` and `FillRule::NonZero`.
+    pub fn with_holes(outer: &[glm::Vec3], holes: &[Vec<glm::Vec3>]) -> Self {
+        let outer_is_clockwise = signed_area(outer) > 0.0;
+
+        let mut edges: Vec<Edge> = ring_edges(outer).collect();
+        for hole in holes {
+            let must_reverse = (signed_area(hole) > 0.0) == outer_is_clockwise;
+
+            edges.extend(ring_edges(hole).map(|mut edge| {
+                if must_reverse {
+                    edge.winding = -edge.winding;
+                }
+                edge
+            }));
+        }
+
+        EdgeTable::from_edges(edges)
+    }
+
+    /// Builds the edge table of the shape made by overlapping every ring in `rings`.
+    ///
+    /// Rings keep their own orientation, so with `FillRule::NonZero` the interior is the union
+    /// of all the rings as long as they are all wound in the same direction.
+    pub fn from_rings(rings: &[Vec<glm::Vec3>]) -> Self {
+        EdgeTable::from_edges(rings.iter().flat_map(|ring| ring_edges(ring)).collect())
+    }
+
+    fn from_edges(mut edges: Vec<Edge>) -> Self {
+        edges.sort_by(|a, b| a.y_top.total_cmp(&b.y_top));
+
+        let y_max = edges
+            .iter()
+            .map(|e| e.y_bottom)
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        EdgeTable { edges, y_max }
+    }
+
+    /// Computes all the spans that make up the interior of the polygon according to `fill_rule`.
+    ///
+    /// Spans are returned from top to bottom and from left to right.
+    pub fn spans(&self, fill_rule: FillRule) -> Vec<Span> {
+        self.sampled_spans(fill_rule, 1)
+    }
+
+    /// Computes the spans of the interior of the polygon on `samples_per_row` evenly spaced
+    /// scanlines inside every pixel row.
+    ///
+    /// With a single sample per row this is the same as `spans`.
+    /// Spans are returned from top to bottom and from left to right.
+    pub fn sampled_spans(&self, fill_rule: FillRule, samples_per_row: u32) -> Vec<Span> {
+        let mut spans = vec![];
+        let Some(first) = self.edges.first() else {
+            return spans;
+        };
+
+        let step = 1.0 / samples_per_row as f32;
+        let first_offset = step / 2.0 - 0.5;
+        let mut sample = ((first.y_top - first_offset) / step).ceil();
+
+        let mut pending = self.edges.iter().peekable();
+        let mut active: Vec<&Edge> = vec![];
+        let mut crossings: Vec<(f32, i32)> = vec![];
+
+        loop {
+            let y = first_offset + sample * step;
+            if y >= self.y_max {
+                break;
+            }
+
+            while let Some(edge) = pending.next_if(|e| e.y_top <= y) {
+                active.push(edge);
+            }
+            active.retain(|e| e.y_top <= y && y < e.y_bottom);
+
+            crossings.clear();
+            crossings.extend(active.iter().map(|e| (e.x_at(y), e.winding)));
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut winding = 0;
+            let mut x_start = 0.0;
+            for &(x, edge_winding) in &crossings {
+                let was_inside = is_inside(winding, fill_rule);
+                winding += edge_winding;
+
+                match (was_inside, is_inside(winding, fill_rule)) {
+                    (false, true) => x_start = x,
+                    (true, false) => spans.push(Span {
+                        y,
+                        x_start,
+                        x_end: x,
+                    }),
+                    _ => {}
+                }
+            }
+
+            sample += 1.0;
+        }
+
+        spans
+    }
+
+    /// Computes how much of each pixel is covered by the interior of the polygon.
+    ///
+    /// The vertical coverage is estimated with `samples_per_row` scanlines per pixel row,
+    /// while the horizontal coverage of each scanline is computed exactly.
+    /// Only rows that are at least partially covered are returned, from top to bottom.
+    pub fn coverage(&self, fill_rule: FillRule, samples_per_row: u32) -> Vec<CoverageRow> {
+        let spans = self.sampled_spans(fill_rule, samples_per_row);
+        let sample_weight = 1.0 / samples_per_row as f32;
+
+        spans
+            .chunk_by(|a, b| a.y.round() == b.y.round())
+            .map(|row_spans| {
+                let x_start = row_spans
+                    .iter()
+                    .map(|s| (s.x_start + 0.5).floor())
+                    .fold(f32::INFINITY, f32::min);
+                let x_end = row_spans
+                    .iter()
+                    .map(|s| (s.x_end + 0.5).floor())
+                    .fold(f32::NEG_INFINITY, f32::max);
+                let mut coverage = vec![0.0; (x_end - x_start) as usize + 1];
+
+                for span in row_spans {
+                    let first_pixel = (span.x_start + 0.5).floor();
+                    let last_pixel = (span.x_end + 0.5).floor();
+
+                    let mut pixel = first_pixel;
+                    while pixel <= last_pixel {
+                        let overlap = span.x_end.min(pixel + 0.5) - span.x_start.max(pixel - 0.5);
+                        coverage[(pixel - x_start) as usize] += overlap.max(0.0) * sample_weight;
+                        pixel += 1.0;
+                    }
+                }
+
+                CoverageRow {
+                    y: row_spans[0].y.round(),
+                    x_start,
+                    coverage,
+                }
+            })
+            .collect()
+    }
+}
+
+/// The fraction of each pixel of a row covered by a polygon.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoverageRow {
+    pub y: f32,
+    /// The x coordinate of the pixel whose coverage is the first one on `coverage`.
+    pub x_start: f32,
+    /// The coverage of each consecutive pixel, between 0 and 1.
+    pub coverage: Vec<f32>,
+}
+
+/// Computes the edges of the closed ring formed by `points`.
+fn ring_edges(points: &[glm::Vec3]) -> impl Iterator<Item = Edge> + '_ {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .filter_map(|(a, b)| Edge::new(a, b))
+}
+
+/// Checks if a region with the given winding number is part of the interior.
+fn is_inside(winding: i32, fill_rule: FillRule) -> bool {
+    match fill_rule {
+        FillRule::EvenOdd => winding % 2 != 0,
+        FillRule::NonZero => winding != 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concave_pocket_is_not_filled() {
+        // A "U" shape, the pocket goes from x=4 to x=6 and from y=0 to y=6.
+        let points = vec![
+            glm::Vec3::new(0.0, 0.0, 0.0),
+            glm::Vec3::new(4.0, 0.0, 0.0),
+            glm::Vec3::new(4.0, 6.0, 0.0),
+            glm::Vec3::new(6.0, 6.0, 0.0),
+            glm::Vec3::new(6.0, 0.0, 0.0),
+            glm::Vec3::new(10.0, 0.0, 0.0),
+            glm::Vec3::new(10.0, 10.0, 0.0),
+            glm::Vec3::new(0.0, 10.0, 0.0),
+        ];
+
+        let spans = EdgeTable::new(&points).spans(FillRule::EvenOdd);
+        let row_3: Vec<&Span> = spans.iter().filter(|s| s.y == 3.0).collect();
+        let row_8: Vec<&Span> = spans.iter().filter(|s| s.y == 8.0).collect();
+
+        assert_eq!(row_3.len(), 2);
+        assert_eq!((row_3[0].x_start, row_3[0].x_end), (0.0, 4.0));
+        assert_eq!((row_3[1].x_start, row_3[1].x_end), (6.0, 10.0));
+
+        assert_eq!(row_8.len(), 1);
+        assert_eq!((row_8[0].x_start, row_8[0].x_end), (0.0, 10.0));
+    }
+
+    #[test]
+    fn test_shared_vertex_is_counted_once() {
+        let points = vec![
+            glm::Vec3::new(5.0, 0.0, 0.0),
+            glm::Vec3::new(10.0, 5.0, 0.0),
+            glm::Vec3::new(5.0, 10.0, 0.0),
+            glm::Vec3::new(0.0, 5.0, 0.0),
+        ];
+
+        let spans = EdgeTable::new(&points).spans(FillRule::EvenOdd);
+
+        assert_eq!(spans.len(), 10);
+        let middle = spans.iter().find(|s| s.y == 5.0).unwrap();
+        assert_eq!((middle.x_start, middle.x_end), (0.0, 10.0));
+    }
+
+    #[test]
+    fn test_fill_rules_on_self_intersecting_polygon() {
+        // A pentagram, its center is wound twice.
+        let points = vec![
+            glm::Vec3::new(50.0, 0.0, 0.0),
+            glm::Vec3::new(80.0, 90.0, 0.0),
+            glm::Vec3::new(5.0, 35.0, 0.0),
+            glm::Vec3::new(95.0, 35.0, 0.0),
+            glm::Vec3::new(20.0, 90.0, 0.0),
+        ];
+        let edge_table = EdgeTable::new(&points);
+
+        let even_odd: Vec<Span> = edge_table
+            .spans(FillRule::EvenOdd)
+            .into_iter()
+            .filter(|s| s.y == 50.0)
+            .collect();
+        let non_zero: Vec<Span> = edge_table
+            .spans(FillRule::NonZero)
+            .into_iter()
+            .filter(|s| s.y == 50.0)
+            .collect();
+
+        assert_eq!(even_odd.len(), 2);
+        assert!(even_odd[0].x_end < 50.0 && 50.0 < even_odd[1].x_start);
+
+        assert_eq!(non_zero.len(), 1);
+        assert_eq!(non_zero[0].x_start, even_odd[0].x_start);
+        assert_eq!(non_zero[0].x_end, even_odd[1].x_end);
+    }
+
+    #[test]
+    fn test_holes_are_empty_regardless_of_orientation() {
+        let outer = vec![
+            glm::Vec3::new(0.0, 0.0, 0.0),
+            glm::Vec3::new(10.0, 0.0, 0.0),
+            glm::Vec3::new(10.0, 10.0, 0.0),
+            glm::Vec3::new(0.0, 10.0, 0.0),
+        ];
+        // Same orientation as the outer ring.
+        let hole = vec![
+            glm::Vec3::new(3.0, 3.0, 0.0),
+            glm::Vec3::new(7.0, 3.0, 0.0),
+            glm::Vec3::new(7.0, 7.0, 0.0),
+            glm::Vec3::new(3.0, 7.0, 0.0),
+        ];
+        let edge_table = EdgeTable::with_holes(&outer, &[hole]);
+
+        for fill_rule in [FillRule::EvenOdd, FillRule::NonZero] {
+            let row_5: Vec<Span> = edge_table
+                .spans(fill_rule)
+                .into_iter()
+                .filter(|s| s.y == 5.0)
+                .collect();
+
+            assert_eq!(row_5.len(), 2);
+            assert_eq!((row_5[0].x_start, row_5[0].x_end), (0.0, 3.0));
+            assert_eq!((row_5[1].x_start, row_5[1].x_end), (7.0, 10.0));
+        }
+    }
+
+    #[test]
+    fn test_coverage_of_half_pixel_edges() {
+        // Pixels are centered on integer coordinates, so the left and right columns and the
+        // top and bottom rows are half covered.
+        let points = vec![
+            glm::Vec3::new(1.0, 1.0, 0.0),
+            glm::Vec3::new(4.0, 1.0, 0.0),
+            glm::Vec3::new(4.0, 4.0, 0.0),
+            glm::Vec3::new(1.0, 4.0, 0.0),
+        ];
+
+        let rows = EdgeTable::new(&points).coverage(FillRule::NonZero, 4);
+
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0].y, 1.0);
+        assert_eq!(rows[0].x_start, 1.0);
+        assert_eq!(rows[0].coverage, vec![0.25, 0.5, 0.5, 0.25]);
+        assert_eq!(rows[1].coverage, vec![0.5, 1.0, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn test_adjacent_squares_tile_without_gaps_or_overlap() {
+        for (origin, size) in [(0.0, 4.0), (0.5, 3.0), (0.25, 2.5)] {
+            let mut painted = [[0; 20]; 20];
+
+            for row in 0..4 {
+                for col in 0..4 {
+                    let left = origin + col as f32 * size;
+                    let top = origin + row as f32 * size;
+                    let square = vec![
+                        glm::Vec3::new(left, top, 0.0),
+                        glm::Vec3::new(left + size, top, 0.0),
+                        glm::Vec3::new(left + size, top + size, 0.0),
+                        glm::Vec3::new(left, top + size, 0.0),
+                    ];
+
+                    for span in EdgeTable::new(&square).spans(FillRule::NonZero) {
+                        for x in span.pixels() {
+                            painted[span.y as usize][x as usize] += 1;
+                        }
+                    }
+                }
+            }
+
+            let end = origin + 4.0 * size;
+            for (y, row) in painted.iter().enumerate() {
+                for (x, count) in row.iter().enumerate() {
+                    let inside =
+                        (origin..end).contains(&(x as f32)) && (origin..end).contains(&(y as f32));
+
+                    assert_eq!(
+                        *count, inside as i32,
+                        "pixel ({x}, {y}) of grid at {origin}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_triangles_sharing_a_diagonal_tile() {
+        let a = glm::Vec3::new(0.0, 0.0, 0.0);
+        let b = glm::Vec3::new(7.0, 1.0, 0.0);
+        let c = glm::Vec3::new(8.0, 8.0, 0.0);
+        let d = glm::Vec3::new(1.0, 7.0, 0.0);
+        let mut painted = [[0; 10]; 10];
+
+        for triangle in [[a, b, c], [a, c, d]] {
+            for span in EdgeTable::new(&triangle).spans(FillRule::NonZero) {
+                for x in span.pixels() {
+                    painted[span.y as usize][x as usize] += 1;
+                }
+            }
+        }
+
+        assert!(painted.iter().flatten().all(|count| *count <= 1));
+        assert_eq!(painted[4][4], 1);
+    }
+}
@@ -0,0 +1,173 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use crate::curve::flatten_arc;
+
+/// Computes the vertices of the regular polygon with the given amount of `sides`
+/// inscribed in the circle centered on `center`.
+///
+/// * `rotation`: Angle in radians the polygon is turned clockwise on the screen.
+///   Without rotation the first vertex is right above `center`.
+pub fn regular_polygon(
+    center: glm::Vec3,
+    radius: f32,
+    sides: usize,
+    rotation: f32,
+) -> Vec<glm::Vec3> {
+    (0..sides)
+        .map(|i| {
+            polar(
+                center,
+                radius,
+                rotation + 2.0 * PI * i as f32 / sides as f32,
+            )
+        })
+        .collect()
+}
+
+/// Computes the vertices of a star with the given amount of `points` centered on `center`.
+///
+/// The tips of the star lie on the circle with `outer_radius` and the corners between them
+/// on the circle with `inner_radius`.
+///
+/// * `rotation`: Angle in radians the star is turned clockwise on the screen.
+///   Without rotation the first tip is right above `center`.
+pub fn star(
+    center: glm::Vec3,
+    outer_radius: f32,
+    inner_radius: f32,
+    points: usize,
+    rotation: f32,
+) -> Vec<glm::Vec3> {
+    (0..2 * points)
+        .map(|i| {
+            let radius = if i % 2 == 0 {
+                outer_radius
+            } else {
+                inner_radius
+            };
+            polar(center, radius, rotation + PI * i as f32 / points as f32)
+        })
+        .collect()
+}
+
+/// Computes the corners of the rectangle with the given size centered on `center`.
+///
+/// * `rotation`: Angle in radians the rectangle is turned clockwise on the screen.
+pub fn rectangle(center: glm::Vec3, width: f32, height: f32, rotation: f32) -> Vec<glm::Vec3> {
+    let (half_width, half_height) = (width / 2.0, height / 2.0);
+
+    rotate(
+        vec![
+            glm::Vec3::new(-half_width, -half_height, 0.0),
+            glm::Vec3::new(half_width, -half_height, 0.0),
+            glm::Vec3::new(half_width, half_height, 0.0),
+            glm::Vec3::new(-half_width, half_height, 0.0),
+        ],
+        center,
+        rotation,
+    )
+}
+
+/// Computes the vertices of the rectangle with the given size centered on `center`,
+/// with its corners rounded by quarter circles.
+///
+/// The corners are approximated with straight lines no further than `tolerance` from them.
+///
+/// * `corner_radius`: Radius of the corners, it can't be larger than half the shortest side.
+/// * `rotation`: Angle in radians the rectangle is turned clockwise on the screen.
+pub fn rounded_rectangle(
+    center: glm::Vec3,
+    width: f32,
+    height: f32,
+    corner_radius: f32,
+    rotation: f32,
+    tolerance: f32,
+) -> Vec<glm::Vec3> {
+    let (half_width, half_height) = (width / 2.0, height / 2.0);
+    let radius = corner_radius.clamp(0.0, half_width.min(half_height));
+    if radius == 0.0 {
+        return rectangle(center, width, height, rotation);
+    }
+
+    // Center of each corner along with the angle where its quarter circle starts,
+    // going clockwise from the top left one.
+    let corners = [
+        (-half_width + radius, -half_height + radius, PI),
+        (half_width - radius, -half_height + radius, -FRAC_PI_2),
+        (half_width - radius, half_height - radius, 0.0),
+        (-half_width + radius, half_height - radius, FRAC_PI_2),
+    ];
+    let points = corners
+        .iter()
+        .flat_map(|&(x, y, start_angle)| {
+            let corner = glm::Vec3::new(x, y, 0.0);
+            flatten_arc(corner, radius, radius, start_angle, FRAC_PI_2, tolerance)
+        })
+        .collect();
+
+    rotate(points, center, rotation)
+}
+
+/// Computes the point at `radius` from `center`, turned `angle` radians clockwise
+/// from the direction right above it.
+fn polar(center: glm::Vec3, radius: f32, angle: f32) -> glm::Vec3 {
+    center + glm::Vec3::new(angle.sin(), -angle.cos(), 0.0) * radius
+}
+
+/// Turns `points`, given relative to the origin, clockwise on the screen by `rotation` radians
+/// and moves them to be relative to `center`.
+fn rotate(points: Vec<glm::Vec3>, center: glm::Vec3, rotation: f32) -> Vec<glm::Vec3> {
+    let (sin, cos) = rotation.sin_cos();
+
+    points
+        .into_iter()
+        .map(|p| center + glm::Vec3::new(p.x * cos - p.y * sin, p.x * sin + p.y * cos, 0.0))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signed_area;
+
+    fn assert_close(a: glm::Vec3, b: glm::Vec3) {
+        assert!((a - b).abs().max() < 1e-4, "{a:?} != {b:?}");
+    }
+
+    #[test]
+    fn test_regular_polygon_and_star() {
+        let center = glm::Vec3::new(10.0, 10.0, 0.0);
+
+        let square = regular_polygon(center, 5.0, 4, 0.0);
+        assert_eq!(square.len(), 4);
+        assert_close(square[0], glm::Vec3::new(10.0, 5.0, 0.0));
+        assert_close(square[1], glm::Vec3::new(15.0, 10.0, 0.0));
+
+        let star = star(center, 10.0, 4.0, 5, PI);
+        assert_eq!(star.len(), 10);
+        assert_close(star[0], glm::Vec3::new(10.0, 20.0, 0.0));
+        for (i, p) in star.iter().enumerate() {
+            let expected = if i % 2 == 0 { 10.0 } else { 4.0 };
+            assert!((glm::distance(p, &center) - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_rectangles() {
+        let center = glm::Vec3::new(0.0, 0.0, 0.0);
+
+        let rotated = rectangle(center, 4.0, 2.0, FRAC_PI_2);
+        assert_close(rotated[0], glm::Vec3::new(1.0, -2.0, 0.0));
+        assert_close(rotated[2], glm::Vec3::new(-1.0, 2.0, 0.0));
+
+        let rounded = rounded_rectangle(center, 20.0, 10.0, 3.0, 0.0, 0.01);
+        let area = 20.0 * 10.0 - (4.0 - PI) * 9.0;
+        assert!((signed_area(&rounded).abs() - area).abs() < 1.0);
+        assert!(rounded
+            .iter()
+            .all(|p| p.x.abs() <= 10.0 + 1e-4 && p.y.abs() <= 5.0 + 1e-4));
+        assert!(
+            signed_area(&rounded) > 0.0 && signed_area(&rectangle(center, 2.0, 2.0, 0.0)) > 0.0
+        );
+    }
+}
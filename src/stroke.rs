@@ -0,0 +1,625 @@
+use crate::{
+    clipping::{clip_line, Rect},
+    curve::flatten_ellipse,
+    signed_area,
+};
+
+/// Maximum distance in pixels between a round join or cap and the polygon approximating it.
+const ROUND_TOLERANCE: f32 = 0.25;
+
+/// Shape used to connect two consecutive segments of a stroke.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineJoin {
+    /// Extends the outer sides of both segments until they meet, falling back to
+    /// `LineJoin::Bevel` when the corner is sharper than the `miter_limit` allows.
+    #[default]
+    Miter,
+    /// Rounds the corner with a circle as wide as the stroke.
+    Round,
+    /// Cuts the corner with a straight line between the outer sides of both segments.
+    Bevel,
+}
+
+/// Shape used at the ends of an open stroke.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineCap {
+    /// The stroke ends exactly at the endpoint.
+    #[default]
+    Butt,
+    /// The stroke extends past the endpoint by half its width.
+    Square,
+    /// The stroke ends with a half circle centered on the endpoint.
+    Round,
+}
+
+/// How lines and polygon outlines are drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrokeStyle {
+    pub width: f32,
+    pub join: LineJoin,
+    pub cap: LineCap,
+    /// Maximum ratio between the length of a miter join and the width of the stroke.
+    pub miter_limit: f32,
+    /// Lengths of the alternating dashes and gaps of the stroke.
+    /// An array with an odd amount of lengths is repeated to make it even.
+    pub dash_array: Vec<f32>,
+    /// How far into the dash pattern the stroke starts.
+    pub dash_offset: f32,
+}
+
+impl StrokeStyle {
+    /// Checks if the stroke has a usable dash pattern.
+    ///
+    /// Patterns with negative lengths or whose lengths add up to zero draw solid strokes.
+    pub fn is_dashed(&self) -> bool {
+        self.dash_array.iter().all(|length| *length >= 0.0)
+            && self.dash_array.iter().sum::<f32>() > 0.0
+    }
+}
+
+impl Default for StrokeStyle {
+    fn default() -> Self {
+        StrokeStyle {
+            width: 1.0,
+            join: LineJoin::default(),
+            cap: LineCap::default(),
+            miter_limit: 4.0,
+            dash_array: vec![],
+            dash_offset: 0.0,
+        }
+    }
+}
+
+/// Splits the path formed by `points` into the dashes described by `dash_array`.
+///
+/// The pattern continues from one segment of the path to the next, so a dash going through
+/// a vertex keeps the vertex and can be joined like the rest of the path.
+/// Each dash is returned as an open path.
+///
+/// Only the dashes inside `bounds` are returned, cut where the path leaves it. The pattern
+/// skips over the rest of the path without splitting it, so long paths that are mostly
+/// outside of `bounds` don't make more dashes than the visible ones.
+///
+/// * `closed`: Whether the path goes back from the last point to the first one.
+/// * `dash_array`: Lengths of the alternating dashes and gaps, it must be a usable pattern
+///   according to `StrokeStyle::is_dashed`.
+/// * `dash_offset`: How far into the pattern the path starts.
+pub fn dash_path(
+    points: &[glm::Vec3],
+    closed: bool,
+    dash_array: &[f32],
+    dash_offset: f32,
+    bounds: &Rect,
+) -> Vec<Vec<glm::Vec3>> {
+    let pattern = if dash_array.len() % 2 == 1 {
+        dash_array.repeat(2)
+    } else {
+        dash_array.to_vec()
+    };
+
+    let mut path = points.to_vec();
+    if closed {
+        path.extend(points.first());
+    }
+    path.dedup();
+
+    let mut phase = DashPhase {
+        index: 0,
+        left: pattern[0],
+    };
+    phase.skip(&pattern, dash_offset);
+
+    let mut dashes = vec![];
+    let mut dash = vec![];
+
+    for segment in path.windows(2) {
+        let Some((start, end)) = clip_line(segment[0], segment[1], bounds) else {
+            if !dash.is_empty() {
+                dashes.push(std::mem::take(&mut dash));
+            }
+            phase.skip(&pattern, (segment[1] - segment[0]).norm());
+            continue;
+        };
+
+        let hidden_before = (start - segment[0]).norm();
+        if hidden_before > 0.0 {
+            if !dash.is_empty() {
+                dashes.push(std::mem::take(&mut dash));
+            }
+            phase.skip(&pattern, hidden_before);
+        }
+
+        let length = (end - start).norm();
+        let point_at = |travelled: f32| start + (end - start) * (travelled / length);
+        let mut travelled = 0.0;
+
+        loop {
+            let is_dash = phase.is_dash();
+            if is_dash && dash.is_empty() {
+                dash.push(point_at(travelled));
+            }
+
+            let step = phase.left.min(length - travelled);
+            travelled += step;
+            phase.left -= step;
+
+            if is_dash && step > 0.0 {
+                dash.push(point_at(travelled));
+            }
+
+            if phase.left > 0.0 {
+                break;
+            }
+
+            if is_dash {
+                dashes.push(std::mem::take(&mut dash));
+            }
+            phase.next(&pattern);
+
+            if travelled >= length {
+                break;
+            }
+        }
+
+        let hidden_after = (segment[1] - end).norm();
+        if hidden_after > 0.0 {
+            if !dash.is_empty() {
+                dashes.push(std::mem::take(&mut dash));
+            }
+            phase.skip(&pattern, hidden_after);
+        }
+    }
+
+    if !dash.is_empty() {
+        dashes.push(dash);
+    }
+
+    dashes
+}
+
+/// Where a path is inside a dash pattern.
+struct DashPhase {
+    /// The dash or gap of the pattern the path is in, dashes have even indices.
+    index: usize,
+    /// How much of the current dash or gap is left.
+    left: f32,
+}
+
+impl DashPhase {
+    fn is_dash(&self) -> bool {
+        self.index.is_multiple_of(2)
+    }
+
+    /// Moves on to the start of the next dash or gap.
+    fn next(&mut self, pattern: &[f32]) {
+        self.index = (self.index + 1) % pattern.len();
+        self.left = pattern[self.index];
+    }
+
+    /// Moves `distance` further into the pattern.
+    ///
+    /// Whole repetitions of the pattern are skipped at once, so the work doesn't grow
+    /// with the distance.
+    fn skip(&mut self, pattern: &[f32], distance: f32) {
+        let mut distance = distance.rem_euclid(pattern.iter().sum());
+
+        while distance > 0.0 {
+            if distance >= self.left {
+                distance -= self.left;
+                self.next(pattern);
+            } else {
+                self.left -= distance;
+                distance = 0.0;
+            }
+        }
+    }
+}
+
+/// Computes the polygons that together cover the stroke of the path formed by `points`.
+///
+/// The path goes through every point in order and, when `closed` is true, back to the first one.
+/// Closed paths get joins on every vertex while open paths get caps on both ends.
+/// Every polygon is wound in the same direction as the ones made by `segment_outline`,
+/// so filling all of them with `FillRule::NonZero` paints the stroke exactly once.
+pub fn stroke_outlines(
+    points: &[glm::Vec3],
+    closed: bool,
+    style: &StrokeStyle,
+) -> Vec<Vec<glm::Vec3>> {
+    let mut points = points.to_vec();
+    points.dedup();
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+
+    let half_width = style.width / 2.0;
+    let Some(first) = points.first() else {
+        return vec![];
+    };
+
+    if points.len() == 1 {
+        // A path without length is only visible through its caps.
+        return match style.cap {
+            LineCap::Butt => vec![],
+            LineCap::Square => vec![oriented(vec![
+                first + glm::Vec3::new(-half_width, -half_width, 0.0),
+                first + glm::Vec3::new(half_width, -half_width, 0.0),
+                first + glm::Vec3::new(half_width, half_width, 0.0),
+                first + glm::Vec3::new(-half_width, half_width, 0.0),
+            ])],
+            LineCap::Round => vec![circle(*first, half_width)],
+        };
+    }
+
+    let segments: Vec<(glm::Vec3, glm::Vec3)> = if closed {
+        points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(a, b)| (*a, *b))
+            .collect()
+    } else {
+        points.windows(2).map(|ps| (ps[0], ps[1])).collect()
+    };
+
+    let mut outlines: Vec<Vec<glm::Vec3>> = segments
+        .iter()
+        .filter_map(|(start, end)| segment_outline(*start, *end, style.width))
+        .collect();
+
+    let joints = if closed {
+        segments.len()
+    } else {
+        segments.len() - 1
+    };
+    outlines.extend((0..joints).filter_map(|i| {
+        let (before, vertex) = segments[i];
+        let (_, after) = segments[(i + 1) % segments.len()];
+
+        join_outline(before, vertex, after, style)
+    }));
+
+    if !closed {
+        let (start, second) = segments[0];
+        let (second_to_last, end) = segments[segments.len() - 1];
+
+        outlines.extend(cap_outline(start, start - second, style));
+        outlines.extend(cap_outline(end, end - second_to_last, style));
+    }
+
+    outlines
+}
+
+/// Computes the rectangle covered by a stroke of the given `width` going from `start` to `end`.
+///
+/// The rectangle is centered on the segment and ends exactly at its endpoints.
+/// Every rectangle built by this function is wound in the same direction,
+/// so overlapping them with `FillRule::NonZero` paints their union.
+///
+/// Returns `None` when both endpoints are the same, since the stroke would have no direction.
+pub fn segment_outline(start: glm::Vec3, end: glm::Vec3, width: f32) -> Option<Vec<glm::Vec3>> {
+    let direction = end - start;
+    if direction.x == 0.0 && direction.y == 0.0 {
+        return None;
+    }
+
+    let normal = glm::Vec3::new(-direction.y, direction.x, 0.0).normalize() * (width / 2.0);
+
+    Some(vec![
+        start + normal,
+        end + normal,
+        end - normal,
+        start - normal,
+    ])
+}
+
+/// Computes the polygon that fills the outer side of the corner at `vertex`,
+/// between the segment coming from `before` and the one going to `after`.
+fn join_outline(
+    before: glm::Vec3,
+    vertex: glm::Vec3,
+    after: glm::Vec3,
+    style: &StrokeStyle,
+) -> Option<Vec<glm::Vec3>> {
+    let half_width = style.width / 2.0;
+    let incoming = (vertex - before).normalize();
+    let outgoing = (after - vertex).normalize();
+    let turn = incoming.x * outgoing.y - incoming.y * outgoing.x;
+
+    if style.join == LineJoin::Round {
+        return Some(circle(vertex, half_width));
+    }
+
+    if turn == 0.0 {
+        // Segments going straight on don't leave a gap, the ones going back have no outer side.
+        return None;
+    }
+
+    // The outer side of the corner is the opposite of the side the path turns to.
+    let side = -turn.signum() * half_width;
+    let outer_in = vertex + glm::Vec3::new(-incoming.y, incoming.x, 0.0) * side;
+    let outer_out = vertex + glm::Vec3::new(-outgoing.y, outgoing.x, 0.0) * side;
+
+    let bevel = vec![vertex, outer_in, outer_out];
+    if style.join == LineJoin::Bevel {
+        return Some(oriented(bevel));
+    }
+
+    // The miter ratio is 1 / sin(angle / 2), where angle is the one between both segments.
+    let half_angle_sin = ((1.0 - incoming.dot(&-outgoing)) / 2.0).sqrt();
+    if half_angle_sin == 0.0 || 1.0 / half_angle_sin > style.miter_limit {
+        return Some(oriented(bevel));
+    }
+
+    let bisector = ((outer_in - vertex) + (outer_out - vertex)).normalize();
+    let tip = vertex + bisector * (half_width / half_angle_sin);
+
+    Some(oriented(vec![vertex, outer_in, tip, outer_out]))
+}
+
+/// Computes the polygon that closes the end of a stroke at `endpoint`.
+///
+/// * `outwards`: The direction going from the path out of the endpoint.
+fn cap_outline(
+    endpoint: glm::Vec3,
+    outwards: glm::Vec3,
+    style: &StrokeStyle,
+) -> Option<Vec<glm::Vec3>> {
+    let half_width = style.width / 2.0;
+
+    match style.cap {
+        LineCap::Butt => None,
+        LineCap::Square => {
+            let tip = endpoint + outwards.normalize() * half_width;
+            segment_outline(endpoint, tip, style.width).map(oriented)
+        }
+        LineCap::Round => Some(circle(endpoint, half_width)),
+    }
+}
+
+/// Approximates the circle with the given `center` and `radius` with a regular polygon.
+fn circle(center: glm::Vec3, radius: f32) -> Vec<glm::Vec3> {
+    oriented(flatten_ellipse(
+        center,
+        radius,
+        radius,
+        0.0,
+        ROUND_TOLERANCE,
+    ))
+}
+
+/// Makes sure `ring` is wound in the same direction as the outlines made by `segment_outline`.
+fn oriented(mut ring: Vec<glm::Vec3>) -> Vec<glm::Vec3> {
+    if signed_area(&ring) > 0.0 {
+        ring.reverse();
+    }
+
+    ring
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVERYWHERE: Rect = Rect {
+        min_x: -100.0,
+        min_y: -100.0,
+        max_x: 100.0,
+        max_y: 100.0,
+    };
+
+    fn style(join: LineJoin, cap: LineCap) -> StrokeStyle {
+        StrokeStyle {
+            width: 4.0,
+            join,
+            cap,
+            ..StrokeStyle::default()
+        }
+    }
+
+    fn reaches(outlines: &[Vec<glm::Vec3>], point: glm::Vec3) -> bool {
+        outlines.iter().flatten().any(|p| (p - point).norm() < 1e-4)
+    }
+
+    #[test]
+    fn test_segment_outline_has_the_stroke_width() {
+        let start = glm::Vec3::new(0.0, 0.0, 0.0);
+        let end = glm::Vec3::new(3.0, 4.0, 0.0);
+
+        let outline = segment_outline(start, end, 2.0).unwrap();
+
+        assert!((signed_area(&outline).abs() - 10.0).abs() < 1e-4);
+        assert!(((outline[0] - outline[3]).norm() - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_segment_outlines_share_orientation() {
+        let center = glm::Vec3::new(0.0, 0.0, 0.0);
+        let ends = [
+            glm::Vec3::new(5.0, 0.0, 0.0),
+            glm::Vec3::new(0.0, 5.0, 0.0),
+            glm::Vec3::new(-5.0, -1.0, 0.0),
+            glm::Vec3::new(2.0, -5.0, 0.0),
+        ];
+
+        let signs: Vec<f32> = ends
+            .iter()
+            .map(|end| signed_area(&segment_outline(center, *end, 3.0).unwrap()).signum())
+            .collect();
+
+        assert!(signs.iter().all(|sign| *sign == signs[0]));
+    }
+
+    #[test]
+    fn test_right_angle_joins() {
+        let path = [
+            glm::Vec3::new(0.0, 0.0, 0.0),
+            glm::Vec3::new(10.0, 0.0, 0.0),
+            glm::Vec3::new(10.0, 10.0, 0.0),
+        ];
+        let outer_corner = glm::Vec3::new(12.0, -2.0, 0.0);
+
+        let miter = stroke_outlines(&path, false, &style(LineJoin::Miter, LineCap::Butt));
+        let bevel = stroke_outlines(&path, false, &style(LineJoin::Bevel, LineCap::Butt));
+        let round = stroke_outlines(&path, false, &style(LineJoin::Round, LineCap::Butt));
+
+        assert!(reaches(&miter, outer_corner));
+        assert!(!reaches(&bevel, outer_corner));
+        assert_eq!(bevel.len(), 3);
+        assert!(round[2]
+            .iter()
+            .all(|p| ((p - path[1]).norm() - 2.0).abs() < 1e-4));
+
+        let orientation = signed_area(&miter[0]).signum();
+        assert!(miter
+            .iter()
+            .chain(&bevel)
+            .chain(&round)
+            .all(|outline| signed_area(outline).signum() == orientation));
+    }
+
+    #[test]
+    fn test_sharp_miter_falls_back_to_bevel() {
+        let path = [
+            glm::Vec3::new(0.0, 0.0, 0.0),
+            glm::Vec3::new(10.0, 0.0, 0.0),
+            glm::Vec3::new(0.0, 1.0, 0.0),
+        ];
+
+        let outlines = stroke_outlines(&path, false, &style(LineJoin::Miter, LineCap::Butt));
+
+        assert_eq!(outlines[2].len(), 3);
+    }
+
+    #[test]
+    fn test_caps_extend_open_paths() {
+        let path = [
+            glm::Vec3::new(0.0, 0.0, 0.0),
+            glm::Vec3::new(10.0, 0.0, 0.0),
+        ];
+
+        let butt = stroke_outlines(&path, false, &style(LineJoin::Miter, LineCap::Butt));
+        let square = stroke_outlines(&path, false, &style(LineJoin::Miter, LineCap::Square));
+        let round = stroke_outlines(&path, false, &style(LineJoin::Miter, LineCap::Round));
+
+        let min_x = |outlines: &[Vec<glm::Vec3>]| {
+            outlines
+                .iter()
+                .flatten()
+                .map(|p| p.x)
+                .fold(f32::INFINITY, f32::min)
+        };
+
+        assert_eq!(butt.len(), 1);
+        assert_eq!(min_x(&butt), 0.0);
+        assert!(reaches(&square, glm::Vec3::new(12.0, 2.0, 0.0)));
+        assert!(reaches(&square, glm::Vec3::new(-2.0, -2.0, 0.0)));
+        assert!((min_x(&round) + 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_closed_paths_have_joins_but_no_caps() {
+        let square = [
+            glm::Vec3::new(0.0, 0.0, 0.0),
+            glm::Vec3::new(10.0, 0.0, 0.0),
+            glm::Vec3::new(10.0, 10.0, 0.0),
+            glm::Vec3::new(0.0, 10.0, 0.0),
+        ];
+
+        let outlines = stroke_outlines(&square, true, &style(LineJoin::Miter, LineCap::Square));
+
+        assert_eq!(outlines.len(), 8);
+        assert!(reaches(&outlines, glm::Vec3::new(-2.0, -2.0, 0.0)));
+    }
+
+    #[test]
+    fn test_dashes_continue_across_vertices() {
+        let path = [
+            glm::Vec3::new(0.0, 0.0, 0.0),
+            glm::Vec3::new(3.0, 0.0, 0.0),
+            glm::Vec3::new(3.0, 10.0, 0.0),
+        ];
+
+        let dashes = dash_path(&path, false, &[4.0, 2.0], 0.0, &EVERYWHERE);
+
+        assert_eq!(
+            dashes,
+            vec![
+                vec![path[0], path[1], glm::Vec3::new(3.0, 1.0, 0.0)],
+                vec![glm::Vec3::new(3.0, 3.0, 0.0), glm::Vec3::new(3.0, 7.0, 0.0)],
+                vec![
+                    glm::Vec3::new(3.0, 9.0, 0.0),
+                    glm::Vec3::new(3.0, 10.0, 0.0)
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dash_offset_and_odd_patterns() {
+        let path = [
+            glm::Vec3::new(0.0, 0.0, 0.0),
+            glm::Vec3::new(10.0, 0.0, 0.0),
+        ];
+
+        // [3] behaves like [3, 3], starting 4 pixels into it leaves 2 pixels of gap.
+        let dashes = dash_path(&path, false, &[3.0], 4.0, &EVERYWHERE);
+
+        assert_eq!(
+            dashes,
+            vec![
+                vec![glm::Vec3::new(2.0, 0.0, 0.0), glm::Vec3::new(5.0, 0.0, 0.0)],
+                vec![
+                    glm::Vec3::new(8.0, 0.0, 0.0),
+                    glm::Vec3::new(10.0, 0.0, 0.0)
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dashes_outside_the_bounds_are_skipped() {
+        let path = [
+            glm::Vec3::new(-1.0e7, 5.0, 0.0),
+            glm::Vec3::new(1.0e7, 5.0, 0.0),
+        ];
+        let bounds = Rect {
+            min_x: 0.0,
+            min_y: 0.0,
+            max_x: 10.0,
+            max_y: 10.0,
+        };
+
+        let dashes = dash_path(&path, false, &[3.0, 1.0], 1.0, &bounds);
+
+        // The pattern picks up 1e7 + 1 pixels in, which is 1 pixel into its first dash.
+        assert_eq!(
+            dashes,
+            vec![
+                vec![glm::Vec3::new(0.0, 5.0, 0.0), glm::Vec3::new(2.0, 5.0, 0.0)],
+                vec![glm::Vec3::new(3.0, 5.0, 0.0), glm::Vec3::new(6.0, 5.0, 0.0)],
+                vec![
+                    glm::Vec3::new(7.0, 5.0, 0.0),
+                    glm::Vec3::new(10.0, 5.0, 0.0)
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dashes_on_closed_paths_include_the_closing_edge() {
+        let square = [
+            glm::Vec3::new(0.0, 0.0, 0.0),
+            glm::Vec3::new(4.0, 0.0, 0.0),
+            glm::Vec3::new(4.0, 4.0, 0.0),
+            glm::Vec3::new(0.0, 4.0, 0.0),
+        ];
+
+        let dashes = dash_path(&square, true, &[2.0, 2.0], 0.0, &EVERYWHERE);
+
+        assert_eq!(dashes.len(), 4);
+        assert_eq!(
+            dashes[3],
+            vec![glm::Vec3::new(0.0, 4.0, 0.0), glm::Vec3::new(0.0, 2.0, 0.0)]
+        );
+    }
+}
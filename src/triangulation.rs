@@ -0,0 +1,139 @@
+use crate::{edge_function, signed_area};
+
+/// Splits a simple polygon into triangles using ear clipping.
+///
+/// Returns the indices inside `points` of the corners of each triangle.
+/// Every triangle keeps the same orientation as the polygon.
+/// Repeated consecutive vertices and vertices in the middle of a straight edge are skipped,
+/// so the resulting triangles never have zero area.
+///
+/// * `points`: The vertices of the polygon, the same ones given to `Framebuffer::polygon`.
+pub fn triangulate(points: &[glm::Vec3]) -> Vec<[usize; 3]> {
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    remaining.dedup_by(|a, b| points[*a] == points[*b]);
+    while remaining.len() > 1 && points[remaining[0]] == points[remaining[remaining.len() - 1]] {
+        remaining.pop();
+    }
+
+    let orientation = signed_area(points).signum();
+    let mut triangles = vec![];
+    let mut current = 0;
+    let mut tries_left = remaining.len();
+
+    while remaining.len() >= 3 && tries_left > 0 {
+        let count = remaining.len();
+        let prev = remaining[(current + count - 1) % count];
+        let ear = remaining[current % count];
+        let next = remaining[(current + 1) % count];
+        let turn = edge_function(&points[prev], &points[ear], &points[next]) * orientation;
+
+        if turn == 0.0 {
+            // A collinear or spike vertex doesn't add any area, so it can be dropped right away.
+            remaining.remove(current % count);
+            tries_left = remaining.len();
+        } else if turn > 0.0 && is_ear(points, &remaining, [prev, ear, next], orientation) {
+            triangles.push([prev, ear, next]);
+            remaining.remove(current % count);
+            tries_left = remaining.len();
+        } else {
+            current += 1;
+            tries_left -= 1;
+        }
+
+        if !remaining.is_empty() {
+            current %= remaining.len();
+        }
+    }
+
+    triangles
+}
+
+/// Checks that no other vertex of the polygon lies inside the given `triangle`.
+fn is_ear(
+    points: &[glm::Vec3],
+    remaining: &[usize],
+    triangle: [usize; 3],
+    orientation: f32,
+) -> bool {
+    let [a, b, c] = triangle.map(|i| points[i]);
+
+    remaining
+        .iter()
+        .filter(|i| !triangle.contains(i))
+        .map(|i| points[*i])
+        .filter(|p| *p != a && *p != b && *p != c)
+        .all(|p| {
+            let inside = edge_function(&a, &b, &p) * orientation >= 0.0
+                && edge_function(&b, &c, &p) * orientation >= 0.0
+                && edge_function(&c, &a, &p) * orientation >= 0.0;
+
+            !inside
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangles_area(points: &[glm::Vec3], triangles: &[[usize; 3]]) -> f32 {
+        triangles
+            .iter()
+            .map(|t| signed_area(&t.map(|i| points[i])))
+            .sum()
+    }
+
+    #[test]
+    fn test_concave_polygon_keeps_its_area() {
+        // The teapot drawn on main.rs.
+        let points = vec![
+            glm::Vec3::new(413.0, 177.0, 0.0),
+            glm::Vec3::new(448.0, 159.0, 0.0),
+            glm::Vec3::new(502.0, 88.0, 0.0),
+            glm::Vec3::new(553.0, 53.0, 0.0),
+            glm::Vec3::new(535.0, 36.0, 0.0),
+            glm::Vec3::new(676.0, 37.0, 0.0),
+            glm::Vec3::new(660.0, 52.0, 0.0),
+            glm::Vec3::new(750.0, 145.0, 0.0),
+            glm::Vec3::new(761.0, 179.0, 0.0),
+            glm::Vec3::new(672.0, 192.0, 0.0),
+            glm::Vec3::new(659.0, 214.0, 0.0),
+            glm::Vec3::new(615.0, 214.0, 0.0),
+            glm::Vec3::new(632.0, 230.0, 0.0),
+            glm::Vec3::new(580.0, 230.0, 0.0),
+            glm::Vec3::new(597.0, 215.0, 0.0),
+            glm::Vec3::new(552.0, 214.0, 0.0),
+            glm::Vec3::new(517.0, 144.0, 0.0),
+            glm::Vec3::new(466.0, 180.0, 0.0),
+        ];
+
+        let triangles = triangulate(&points);
+
+        assert_eq!(triangles.len(), points.len() - 2);
+        assert_eq!(triangles_area(&points, &triangles), signed_area(&points));
+        assert!(triangles
+            .iter()
+            .all(|t| signed_area(&t.map(|i| points[i])) * signed_area(&points) > 0.0));
+    }
+
+    #[test]
+    fn test_collinear_and_duplicate_vertices_are_skipped() {
+        let points = vec![
+            glm::Vec3::new(0.0, 0.0, 0.0),
+            glm::Vec3::new(0.0, 0.0, 0.0),
+            glm::Vec3::new(5.0, 0.0, 0.0),
+            glm::Vec3::new(10.0, 0.0, 0.0),
+            glm::Vec3::new(10.0, 10.0, 0.0),
+            glm::Vec3::new(0.0, 10.0, 0.0),
+            glm::Vec3::new(0.0, 0.0, 0.0),
+        ];
+
+        let triangles = triangulate(&points);
+
+        assert!(triangles.len() <= 3);
+        assert_eq!(triangles_area(&points, &triangles), 100.0);
+        assert!(triangles
+            .iter()
+            .all(|t| signed_area(&t.map(|i| points[i])) != 0.0));
+        assert!(triangles.iter().flatten().all(|i| *i != 1 && *i != 6));
+    }
+}